@@ -0,0 +1,54 @@
+use rustracer::prelude::*;
+use std::{
+    f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_6},
+    process::Command,
+};
+
+fn main() {
+    let hex = hexagon().with_transform(identity().rotate_x(-FRAC_PI_6).translation(0, 0.5, 0));
+    let world = world()
+        .with_object(hex)
+        .with_light(point_light(point(-10, 10, -10), color(1, 1, 1)));
+    let camera = camera(500, 250, FRAC_PI_3).with_transform(view_transform(
+        point(0, 1.5, -4),
+        point(0, 0.5, 0),
+        vector(0, 1, 0),
+    ));
+    let canvas = camera.render(&world);
+    canvas
+        .write_png_file("scene.png")
+        .expect("could not write scene");
+    Command::new("open")
+        .arg("scene.png")
+        .output()
+        .expect("could not open scene");
+}
+
+fn hexagon() -> Group {
+    let mut hex = group();
+    for n in 0..6 {
+        let side = hexagon_side().with_transform(rotation_y(n as f64 * FRAC_PI_3));
+        hex.add_child(side);
+    }
+    hex
+}
+
+fn hexagon_side() -> Group {
+    group()
+        .with_child(hexagon_corner())
+        .with_child(hexagon_edge())
+}
+
+fn hexagon_corner() -> Sphere {
+    sphere().with_transform(identity().scaling(0.25, 0.25, 0.25).translation(0, 0, -1))
+}
+
+fn hexagon_edge() -> Cylinder {
+    cylinder().with_minimum(0).with_maximum(1).with_transform(
+        identity()
+            .scaling(0.25, 1, 0.25)
+            .rotate_z(-FRAC_PI_2)
+            .rotate_y(-FRAC_PI_6)
+            .translation(0, 0, -1),
+    )
+}
@@ -0,0 +1,123 @@
+use clap::Parser;
+use rustracer::prelude::*;
+use std::{
+    f64::consts::PI,
+    fs,
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// Renders a Wavefront OBJ model.
+#[derive(Debug, clap::Parser)]
+struct Args {
+    path: PathBuf,
+    /// The most children a group may directly hold once divided.
+    #[arg(long, default_value_t = DEFAULT_DIVIDE_THRESHOLD)]
+    threshold: usize,
+    #[arg(long, default_value_t = 400)]
+    dim: usize,
+    /// Defaults to one thread per core.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Stops the render after this many seconds and saves what is done.
+    #[arg(long)]
+    time_limit: Option<f64>,
+    /// Also writes the unclamped render to scene.exr.
+    #[arg(long)]
+    exr: bool,
+    /// Brightens or darkens scene.png by this many stops.
+    #[arg(long, default_value_t = 0.0)]
+    exposure: f64,
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Clamp)]
+    tone_map: ToneMapArg,
+    /// Encodes scene.png for display with the sRGB curve.
+    #[arg(long)]
+    srgb: bool,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum ToneMapArg {
+    Clamp,
+    Reinhard,
+    Aces,
+    Filmic,
+}
+
+impl Args {
+    fn output_transform(&self) -> OutputTransform {
+        let tone_map = match self.tone_map {
+            ToneMapArg::Clamp => ToneMap::Clamp,
+            ToneMapArg::Reinhard => ToneMap::Reinhard,
+            ToneMapArg::Aces => ToneMap::Aces,
+            ToneMapArg::Filmic => ToneMap::Filmic,
+        };
+        let encoding = if self.srgb {
+            Encoding::Srgb
+        } else {
+            Encoding::Linear
+        };
+        output_transform()
+            .with_exposure(self.exposure)
+            .with_tone_map(tone_map)
+            .with_encoding(encoding)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let obj = ObjFile::from_file(&args.path)?;
+    for line in obj.ignored() {
+        eprintln!("ignored line {}: {}", line.number, line.text);
+    }
+    let mut model = obj.into_group();
+    model.divide(args.threshold);
+
+    // frame the model based on its bounding box
+    let b = model.parent_space_bounds();
+    let center = b.centroid();
+    let size = (b.max() - b.min()).magnitude();
+    let from = center.add_vector(vector(0, size * 0.25, -size));
+    let light = point_light(
+        center.add_vector(vector(-size, size, -size)),
+        color(1, 1, 1),
+    );
+    let world = world().with_object(model).with_light(light);
+    let camera = camera(args.dim, args.dim, PI / 3.0).with_transform(view_transform(
+        from,
+        center,
+        vector(0, 1, 0),
+    ));
+
+    let start = Instant::now();
+    take_stats();
+    let mut renderer = renderer();
+    if let Some(threads) = args.threads {
+        renderer = renderer.with_threads(threads);
+    }
+    let limit = args.time_limit.map(Duration::from_secs_f64);
+    let cancel = CancelToken::new();
+    let rendered = renderer.render_with(&camera, &world, &cancel, |progress| {
+        eprint!("\r{progress}    ");
+        if limit.is_some_and(|limit| progress.elapsed > limit) {
+            cancel.cancel();
+        }
+    });
+    eprintln!();
+    let canvas = rendered.canvas;
+    if rendered.cancelled {
+        println!("render cancelled ({:?})", start.elapsed());
+    } else {
+        println!("render complete ({:?})", start.elapsed());
+    }
+    println!("{}", take_stats());
+
+    let png = png_options().with_output_transform(args.output_transform());
+    fs::write("scene.png", canvas.png_with(&png)?)?;
+    if args.exr {
+        let options = exr_options().with_compression(ExrCompression::Zip);
+        canvas.write_exr_file("scene.exr", &options)?;
+    }
+    Command::new("open").arg("scene.png").output()?;
+    Ok(())
+}
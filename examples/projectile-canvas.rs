@@ -1,5 +1,5 @@
 use rustracer::{canvas::canvas, prelude::*};
-use std::{fs, process::Command};
+use std::process::Command;
 
 fn main() {
     let mut canvas = canvas(900, 550);
@@ -11,17 +11,23 @@ fn main() {
         wind: vector(-0.01, 0, 0),
     };
     let red = color(1, 0, 0);
+    let height = canvas.height as f64;
+    let mut path = vec![];
     loop {
-        canvas.write(p.pos.x() as usize, canvas.height - p.pos.y() as usize, red);
+        // canvas rows count down from the top; the projectile may leave the
+        // canvas before it lands, which the drawing clips
+        path.push((p.pos.x(), height - p.pos.y()));
         p = tick(&e, p);
         if p.pos.y() <= 0.0 {
             break;
         }
     }
-    let ppm = canvas.ppm();
-    fs::write("scene.ppm", ppm).expect("could not write scene");
+    canvas.draw_polyline(&path, red);
+    canvas
+        .write_png_file("scene.png")
+        .expect("could not write scene");
     Command::new("open")
-        .arg("scene.ppm")
+        .arg("scene.png")
         .output()
         .expect("could not open scene");
 }
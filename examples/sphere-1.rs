@@ -1,11 +1,57 @@
 use clap::Parser;
-use rustracer::{canvas::canvas, prelude::*};
-use std::{fs, process::Command, time::Instant};
+use rustracer::prelude::*;
+use std::{f64::consts::PI, process::Command, time::Instant};
 
 #[derive(Debug, clap::Parser)]
 struct Args {
     #[arg(long)]
     alter: bool,
+    /// Defaults to one thread per core.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Fires an n by n grid of rays per pixel to smooth the edges.
+    #[arg(long, default_value_t = 1)]
+    samples: usize,
+    /// Jitters each ray within its cell of the grid.
+    #[arg(long)]
+    jitter: bool,
+    /// Only supersamples pixels that differ from a neighbor by more than
+    /// this much.
+    #[arg(long)]
+    adaptive: Option<f64>,
+    #[arg(long, value_enum, default_value_t = FilterArg::Box)]
+    filter: FilterArg,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum FilterArg {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+}
+
+impl Args {
+    fn sampler(&self) -> Sampler {
+        let supersampling = match (self.samples, self.adaptive) {
+            (n, Some(threshold)) => Supersampling::Adaptive {
+                samples: n,
+                threshold,
+            },
+            (1, None) => Supersampling::Center,
+            (n, None) if self.jitter => Supersampling::Jittered(n),
+            (n, None) => Supersampling::Grid(n),
+        };
+        let filter = match self.filter {
+            FilterArg::Box => Filter::Box,
+            FilterArg::Tent => Filter::Tent,
+            FilterArg::Gaussian => Filter::Gaussian,
+            FilterArg::Mitchell => Filter::Mitchell,
+        };
+        sampler()
+            .with_supersampling(supersampling)
+            .with_filter(filter)
+    }
 }
 
 fn main() {
@@ -14,44 +60,46 @@ fn main() {
     let dim = 500;
     let width = dim;
     let height = dim;
-    let mut canvas = canvas(width, height);
-
+    let s = sphere().with_material(material().with_color(color(1, 0.2, 1)));
     let s = if args.alter {
-        sphere().with_transform(
+        s.with_transform(
             identity()
                 .scaling(1, 0.5, 1)
                 .scaling(0.5, 1.5, 1)
                 .shearing(1, 0, 0, 0, 0, 0),
         )
     } else {
-        sphere()
+        s
     };
-    let wall_z = 10.0;
-    let wall_size = 7.0;
-    let pixel_size = wall_size / (dim as f64);
-    let half = (dim as f64) / 2.0 * pixel_size;
-    let color = color(0.5, 0.25, 0);
-    let ray_p = point(0, 0, -5);
-    for y in 0..dim {
-        let wy = half - pixel_size * (y as f64);
-        for x in 0..dim {
-            let wx = -half + pixel_size * (x as f64);
-            let pos = point(wx, wy, wall_z);
-            let vec = (pos - ray_p).normalize();
-            let r = ray(ray_p, vec);
-            if s.intersect(r).hit().is_some() {
-                canvas.write(x, y, color);
-            }
-        }
+    let light = point_light(point(-10, 10, -10), color(1, 1, 1));
+    let world = world().with_object(s).with_light(light);
+    let camera = camera(width, height, PI / 4.0).with_transform(view_transform(
+        point(0, 0, -5),
+        point(0, 0, 0),
+        vector(0, 1, 0),
+    ));
+    let mut renderer = renderer().with_sampler(args.sampler());
+    if let Some(threads) = args.threads {
+        renderer = renderer.with_threads(threads);
     }
+    let canvas = renderer
+        .render_with(&camera, &world, &CancelToken::new(), |progress| {
+            eprint!("\r{progress}    ")
+        })
+        .canvas;
+    eprintln!();
 
     let elapsed = start.elapsed() / 1000 * 1000;
     let per_pixel = elapsed / ((width * height) as u32);
-    println!("render complete ({elapsed:?}) ({per_pixel:?} / pixel)");
-    let ppm = canvas.ppm();
-    fs::write("scene.ppm", ppm).expect("could not write scene");
+    println!(
+        "render complete ({elapsed:?}) ({per_pixel:?} / pixel, {} threads)",
+        renderer.threads()
+    );
+    canvas
+        .write_png_file("scene.png")
+        .expect("could not write scene");
     assert!(Command::new("open")
-        .args(["scene.ppm"])
+        .args(["scene.png"])
         .output()
         .expect("could not open scene")
         .status
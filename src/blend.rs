@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn blended_pattern(a: impl IntoPattern, b: impl IntoPattern) -> BlendedPattern {
+    BlendedPattern::new(a, b)
+}
+
+/// Averages two patterns, each evaluated in its own pattern space, so that
+/// e.g. two stripe patterns at right angles make a plaid.
+#[derive(Clone, Debug)]
+pub struct BlendedPattern {
+    props: PatternProps,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl BlendedPattern {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for BlendedPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        (self.a.color_at(point) + self.b.color_at(point)).mul_scalar(0.5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn a_blended_pattern_averages_its_patterns() {
+        let white = color(1, 1, 1);
+        let black = color(0, 0, 0);
+        let a = stripe_pattern(white, black);
+        let b = stripe_pattern(white, black).with_transform(rotation_y(FRAC_PI_2));
+        let pattern = blended_pattern(a, b);
+        assert_eq!(pattern.color_at(point(0.5, 0, -0.5)), white);
+        assert_eq!(pattern.color_at(point(1.5, 0, -0.5)), color(0.5, 0.5, 0.5));
+        assert_eq!(pattern.color_at(point(0.5, 0, 0.5)), color(0.5, 0.5, 0.5));
+        assert_eq!(pattern.color_at(point(1.5, 0, 0.5)), black);
+    }
+
+    #[test]
+    fn a_blended_pattern_applies_its_own_transform_first() {
+        let pattern = blended_pattern(
+            color(1, 0, 0),
+            stripe_pattern(color(0, 0, 1), color(0, 0, 0)),
+        )
+        .with_transform(scaling(2, 2, 2));
+        assert_eq!(pattern.color_at(point(1.5, 0, 0)), color(0.5, 0, 0.5));
+        assert_eq!(pattern.color_at(point(2.5, 0, 0)), color(0.5, 0, 0));
+    }
+}
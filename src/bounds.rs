@@ -0,0 +1,382 @@
+use super::prelude::*;
+
+pub fn bounds(min: Point, max: Point) -> Bounds {
+    Bounds::new(min, max)
+}
+
+/// An axis-aligned bounding box. A box that has not had anything added to
+/// it is empty, with its minimum above its maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(
+            point(Num::INFINITY, Num::INFINITY, Num::INFINITY),
+            point(Num::NEG_INFINITY, Num::NEG_INFINITY, Num::NEG_INFINITY),
+        )
+    }
+
+    pub fn infinite() -> Self {
+        Self::new(
+            point(Num::NEG_INFINITY, Num::NEG_INFINITY, Num::NEG_INFINITY),
+            point(Num::INFINITY, Num::INFINITY, Num::INFINITY),
+        )
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min.get(axis) > self.max.get(axis))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|axis| self.min.get(axis).is_finite() && self.max.get(axis).is_finite())
+    }
+
+    pub fn centroid(&self) -> Point {
+        point(
+            (self.min.x() + self.max.x()) / 2.0,
+            (self.min.y() + self.max.y()) / 2.0,
+            (self.min.z() + self.max.z()) / 2.0,
+        )
+    }
+
+    pub fn add_point(&mut self, p: Point) {
+        for axis in 0..3 {
+            let min = self.min.get(axis).min(p.get(axis));
+            let max = self.max.get(axis).max(p.get(axis));
+            self.min.set(axis, min);
+            self.max.set(axis, max);
+        }
+    }
+
+    pub fn with_point(mut self, p: Point) -> Self {
+        self.add_point(p);
+        self
+    }
+
+    pub fn merge(&mut self, other: Bounds) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, p: Point) -> bool {
+        (0..3).all(|axis| self.min.get(axis) <= p.get(axis) && p.get(axis) <= self.max.get(axis))
+    }
+
+    pub fn contains_box(&self, other: Bounds) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// The box in the space that `tf` transforms into. Since the corners
+    /// may rotate, the result bounds all eight of them.
+    pub fn transform(&self, tf: Matrix) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let (lo, hi) = (self.min, self.max);
+        let mut dst = Self::empty();
+        let mut unbounded = [false; 3];
+        for corner in [
+            point(lo.x(), lo.y(), lo.z()),
+            point(lo.x(), lo.y(), hi.z()),
+            point(lo.x(), hi.y(), lo.z()),
+            point(lo.x(), hi.y(), hi.z()),
+            point(hi.x(), lo.y(), lo.z()),
+            point(hi.x(), lo.y(), hi.z()),
+            point(hi.x(), hi.y(), lo.z()),
+            point(hi.x(), hi.y(), hi.z()),
+        ] {
+            let p = transform_corner(tf, corner);
+            for (axis, unbounded) in unbounded.iter_mut().enumerate() {
+                // infinity times zero is NaN, which means an infinite extent
+                // was mixed into this axis
+                *unbounded |= p.get(axis).is_nan();
+            }
+            dst.add_point(p);
+        }
+        for (axis, unbounded) in unbounded.into_iter().enumerate() {
+            if unbounded {
+                dst.min.set(axis, Num::NEG_INFINITY);
+                dst.max.set(axis, Num::INFINITY);
+            }
+        }
+        dst
+    }
+
+    pub fn intersects(&self, ray: Ray) -> bool {
+        let mut tmin = Num::NEG_INFINITY;
+        let mut tmax = Num::INFINITY;
+        for axis in 0..3 {
+            let (amin, amax) = check_axis(
+                ray.origin().get(axis),
+                ray.dir().get(axis),
+                self.min.get(axis),
+                self.max.get(axis),
+            );
+            tmin = tmin.max(amin);
+            tmax = tmax.min(amax);
+        }
+        tmin <= tmax && tmax >= 0.0
+    }
+
+    /// Splits the box in half along its longest axis.
+    pub fn split(&self) -> (Bounds, Bounds) {
+        let axis = self.longest_axis();
+        let mid = (self.min.get(axis) + self.max.get(axis)) / 2.0;
+        let mut left_max = self.max;
+        left_max.set(axis, mid);
+        let mut right_min = self.min;
+        right_min.set(axis, mid);
+        (bounds(self.min, left_max), bounds(right_min, self.max))
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let extent = |axis: usize| self.max.get(axis) - self.min.get(axis);
+        // ties go to the earliest axis
+        (1..3).fold(0, |best, axis| {
+            if extent(axis) > extent(best) {
+                axis
+            } else {
+                best
+            }
+        })
+    }
+}
+
+/// Like `Matrix::mul_point`, but skips the terms of the product where the
+/// matrix entry is zero so that infinite coordinates don't turn into NaN.
+fn transform_corner(tf: Matrix, corner: Point) -> Point {
+    let coord = |row: usize| {
+        let sum: Num = (0..3)
+            .filter(|col| tf.get(row, *col) != 0.0)
+            .map(|col| tf.get(row, col) * corner.get(col))
+            .sum();
+        sum + tf.get(row, 3)
+    };
+    point(coord(0), coord(1), coord(2))
+}
+
+/// Finds where a ray crosses the planes at `min` and `max` along one axis,
+/// returning the nearest crossing first.
+pub(crate) fn check_axis(origin: Num, direction: Num, min: Num, max: Num) -> (Num, Num) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * Num::INFINITY,
+            tmax_numerator * Num::INFINITY,
+        )
+    };
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_4, SQRT_2};
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = Bounds::empty();
+        assert_eq!(b.min(), point(Num::INFINITY, Num::INFINITY, Num::INFINITY));
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn creating_a_bounding_box_with_volume() {
+        let b = bounds(point(-1, -2, -3), point(3, 2, 1));
+        assert_eq!(b.min(), point(-1, -2, -3));
+        assert_eq!(b.max(), point(3, 2, 1));
+        assert!(!b.is_empty());
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let b = Bounds::empty()
+            .with_point(point(-5, 2, 0))
+            .with_point(point(7, 0, -3));
+        assert_eq!(b.min(), point(-5, 0, -3));
+        assert_eq!(b.max(), point(7, 2, 0));
+    }
+
+    #[test]
+    fn adding_one_bounding_box_to_another() {
+        let mut b1 = bounds(point(-5, -2, 0), point(7, 4, 4));
+        let b2 = bounds(point(8, -7, -2), point(14, 2, 8));
+        b1.merge(b2);
+        assert_eq!(b1.min(), point(-5, -7, -2));
+        assert_eq!(b1.max(), point(14, 4, 8));
+        b1.merge(Bounds::empty());
+        assert_eq!(b1.max(), point(14, 4, 8));
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_point() {
+        let b = bounds(point(5, -2, 0), point(11, 4, 7));
+        for (p, result) in [
+            (point(5, -2, 0), true),
+            (point(11, 4, 7), true),
+            (point(8, 1, 3), true),
+            (point(3, 0, 3), false),
+            (point(8, -4, 3), false),
+            (point(8, 1, -1), false),
+            (point(13, 1, 3), false),
+            (point(8, 5, 3), false),
+            (point(8, 1, 8), false),
+        ] {
+            assert_eq!(b.contains_point(p), result, "{p}");
+        }
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_box() {
+        let b = bounds(point(5, -2, 0), point(11, 4, 7));
+        for (min, max, result) in [
+            (point(5, -2, 0), point(11, 4, 7), true),
+            (point(6, -1, 1), point(10, 3, 6), true),
+            (point(4, -3, -1), point(10, 3, 6), false),
+            (point(6, -1, 1), point(12, 5, 8), false),
+        ] {
+            assert_eq!(b.contains_box(bounds(min, max)), result, "{min} {max}");
+        }
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = bounds(point(-1, -1, -1), point(1, 1, 1));
+        let m = rotation_x(FRAC_PI_4).mul_matrix(rotation_y(FRAC_PI_4));
+        let b2 = b.transform(m);
+        assert_eq!(b2.min(), point(-SQRT_2, -1.70711, -1.70711));
+        assert_eq!(b2.max(), point(SQRT_2, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn transforming_an_infinite_bounding_box() {
+        let b = bounds(
+            point(Num::NEG_INFINITY, 0, Num::NEG_INFINITY),
+            point(Num::INFINITY, 0, Num::INFINITY),
+        );
+        let b2 = b.transform(translation(0, 3, 0));
+        assert_eq!(b2.min().y(), 3.0);
+        assert_eq!(b2.max().y(), 3.0);
+        let b3 = b.transform(rotation_x(FRAC_PI_4));
+        assert_eq!(b3, Bounds::infinite());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
+        let b = bounds(point(-1, -1, -1), point(1, 1, 1));
+        for (origin, direction, result) in [
+            (point(5, 0.5, 0), vector(-1, 0, 0), true),
+            (point(-5, 0.5, 0), vector(1, 0, 0), true),
+            (point(0.5, 5, 0), vector(0, -1, 0), true),
+            (point(0.5, -5, 0), vector(0, 1, 0), true),
+            (point(0.5, 0, 5), vector(0, 0, -1), true),
+            (point(0.5, 0, -5), vector(0, 0, 1), true),
+            (point(0, 0.5, 0), vector(0, 0, 1), true),
+            (point(-2, 0, 0), vector(2, 4, 6), false),
+            (point(0, -2, 0), vector(6, 2, 4), false),
+            (point(0, 0, -2), vector(4, 6, 2), false),
+            (point(2, 0, 2), vector(0, 0, -1), false),
+            (point(0, 2, 2), vector(0, -1, 0), false),
+            (point(2, 2, 0), vector(-1, 0, 0), false),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert_eq!(b.intersects(r), result, "{origin} {direction:?}");
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_non_cubic_bounding_box() {
+        let b = bounds(point(5, -2, 0), point(11, 4, 7));
+        for (origin, direction, result) in [
+            (point(15, 1, 2), vector(-1, 0, 0), true),
+            (point(-5, -1, 4), vector(1, 0, 0), true),
+            (point(7, 6, 5), vector(0, -1, 0), true),
+            (point(9, -5, 6), vector(0, 1, 0), true),
+            (point(8, 2, 12), vector(0, 0, -1), true),
+            (point(6, 0, -5), vector(0, 0, 1), true),
+            (point(8, 1, 3.5), vector(0, 0, 1), true),
+            (point(9, -1, -8), vector(2, 4, 6), false),
+            (point(8, 3, -4), vector(6, 2, 4), false),
+            (point(9, -1, -2), vector(4, 6, 2), false),
+            (point(4, 0, 9), vector(0, 0, -1), false),
+            (point(8, 6, -1), vector(0, -1, 0), false),
+            (point(12, 5, 4), vector(-1, 0, 0), false),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert_eq!(b.intersects(r), result, "{origin} {direction:?}");
+        }
+    }
+
+    #[test]
+    fn a_box_behind_the_ray_is_not_intersected() {
+        let b = bounds(point(-1, -1, -1), point(1, 1, 1));
+        let r = ray(point(0, 0, 5), vector(0, 0, 1));
+        assert!(!b.intersects(r));
+    }
+
+    #[test]
+    fn splitting_a_box_along_its_longest_axis() {
+        for (min, max, left_max, right_min) in [
+            (
+                point(-1, -4, -5),
+                point(9, 6, 5),
+                point(4, 6, 5),
+                point(4, -4, -5),
+            ),
+            (
+                point(-1, -2, -3),
+                point(9, 5.5, 3),
+                point(4, 5.5, 3),
+                point(4, -2, -3),
+            ),
+            (
+                point(-1, -2, -3),
+                point(5, 8, 3),
+                point(5, 3, 3),
+                point(-1, 3, -3),
+            ),
+            (
+                point(-1, -2, -3),
+                point(5, 3, 7),
+                point(5, 3, 2),
+                point(-1, -2, 2),
+            ),
+        ] {
+            let (left, right) = bounds(min, max).split();
+            assert_eq!(left, bounds(min, left_max));
+            assert_eq!(right, bounds(right_min, max));
+        }
+    }
+}
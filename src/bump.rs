@@ -0,0 +1,313 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn noise_bump(noise: Noise) -> BumpMap {
+    BumpMap::new(BumpSource::Noise(noise))
+}
+
+pub fn pattern_bump(pattern: impl IntoPattern) -> BumpMap {
+    BumpMap::new(BumpSource::Pattern(pattern.into_pattern()))
+}
+
+pub fn normal_map(texture: ImageTexture, mapping: UvMapping) -> NormalMap {
+    NormalMap::new(texture, mapping)
+}
+
+/// The step used to estimate derivatives by finite differences.
+const DELTA: Num = 0.0001;
+
+/// Bends the normals of a shape to fake surface detail that the geometry
+/// doesn't have. Perturbations are applied to the world space normal and
+/// must return a normalized vector.
+pub trait NormalPerturbation: fmt::Debug + Send + Sync {
+    fn perturb(&self, props: &ShapeProps, world_point: Point, normal: Vector) -> Vector;
+}
+
+/// Where a bump map gets the height of the surface from.
+#[derive(Clone, Debug)]
+pub enum BumpSource {
+    /// Fractal noise.
+    Noise(Noise),
+    /// The brightness of a pattern, from 0 for black to 1 for white.
+    Pattern(Arc<dyn Pattern>),
+}
+
+/// Tilts normals away from the slope of a height field defined in object
+/// space, as if the surface were raised where the height is large.
+#[derive(Clone, Debug)]
+pub struct BumpMap {
+    source: BumpSource,
+    scale: Num,
+    frequency: Num,
+    octaves: usize,
+}
+
+impl BumpMap {
+    pub fn new(source: BumpSource) -> Self {
+        Self {
+            source,
+            scale: 1.0,
+            frequency: 1.0,
+            octaves: 1,
+        }
+    }
+
+    /// How strongly the slope of the height field tilts the normal.
+    pub fn with_scale(mut self, scale: impl Into<Num>) -> Self {
+        self.scale = scale.into();
+        self
+    }
+
+    /// Shrinks (above 1) or stretches (below 1) the height field.
+    pub fn with_frequency(mut self, frequency: impl Into<Num>) -> Self {
+        self.frequency = frequency.into();
+        self
+    }
+
+    /// The number of noise octaves, for noise bumps.
+    pub fn with_octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    fn height(&self, p: Point) -> Num {
+        let p = point(
+            p.x() * self.frequency,
+            p.y() * self.frequency,
+            p.z() * self.frequency,
+        );
+        match &self.source {
+            BumpSource::Noise(noise) => noise.fbm(p, self.octaves),
+            BumpSource::Pattern(pattern) => {
+                let c = pattern.color_at(p);
+                (c.r() + c.g() + c.b()) / 3.0
+            }
+        }
+    }
+}
+
+impl NormalPerturbation for BumpMap {
+    fn perturb(&self, props: &ShapeProps, world_point: Point, normal: Vector) -> Vector {
+        let p = props.world_inverse().mul_point(world_point);
+        let gradient = object_gradient(p, |p| self.height(p));
+        // only the slope across the surface should tilt the normal
+        let gradient = covector_to_world(props, gradient);
+        let slope = gradient - normal.mul_scalar(gradient.dot(normal));
+        (normal - slope.mul_scalar(self.scale)).normalize()
+    }
+}
+
+/// A tangent space normal map: each pixel encodes a normal, with red and
+/// green along the directions in which u and v increase and blue pointing
+/// straight out of the surface. Flat areas are (0.5, 0.5, 1).
+#[derive(Clone, Debug)]
+pub struct NormalMap {
+    texture: ImageTexture,
+    mapping: UvMapping,
+    strength: Num,
+}
+
+impl NormalMap {
+    pub fn new(texture: ImageTexture, mapping: UvMapping) -> Self {
+        Self {
+            texture,
+            mapping,
+            strength: 1.0,
+        }
+    }
+
+    /// Blends between the geometric normal (0) and the mapped one (1).
+    pub fn with_strength(mut self, strength: impl Into<Num>) -> Self {
+        self.strength = strength.into();
+        self
+    }
+}
+
+impl NormalPerturbation for NormalMap {
+    fn perturb(&self, props: &ShapeProps, world_point: Point, normal: Vector) -> Vector {
+        let p = props.world_inverse().mul_point(world_point);
+        let (u, v) = self.mapping.map(p);
+        let c = self.texture.uv_color_at(u, v);
+        let mapped = vector(2.0 * c.r() - 1.0, 2.0 * c.g() - 1.0, 2.0 * c.b() - 1.0);
+        // the tangent frame follows the direction in which u increases
+        let u_of = |p: Point| self.mapping.map(p).0;
+        let v_of = |p: Point| self.mapping.map(p).1;
+        let du = covector_to_world(props, uv_gradient(p, u_of));
+        let dv = covector_to_world(props, uv_gradient(p, v_of));
+        let tangent = du - normal.mul_scalar(du.dot(normal));
+        let tangent = if tangent.magnitude() < EPSILON {
+            any_perpendicular(normal)
+        } else {
+            tangent.normalize()
+        };
+        let mut bitangent = normal.cross(tangent);
+        if bitangent.dot(dv) < 0.0 {
+            bitangent = -bitangent;
+        }
+        let perturbed = tangent.mul_scalar(mapped.x())
+            + bitangent.mul_scalar(mapped.y())
+            + normal.mul_scalar(mapped.z());
+        (normal + (perturbed.normalize() - normal).mul_scalar(self.strength)).normalize()
+    }
+}
+
+/// Estimates the gradient of `f` at `p` by forward differences.
+fn object_gradient(p: Point, f: impl Fn(Point) -> Num) -> Vector {
+    forward_differences(p, f, |d| d)
+}
+
+/// The gradient of a texture coordinate. Jumps of more than half a unit
+/// are assumed to be the coordinate wrapping around, and are unwrapped.
+fn uv_gradient(p: Point, f: impl Fn(Point) -> Num) -> Vector {
+    forward_differences(p, f, |d| (d + 0.5).rem_euclid(1.0) - 0.5)
+}
+
+fn forward_differences(p: Point, f: impl Fn(Point) -> Num, adjust: impl Fn(Num) -> Num) -> Vector {
+    let h = f(p);
+    let diff = |offset: Vector| adjust(f(p.add_vector(offset)) - h);
+    let dx = diff(vector(DELTA, 0, 0));
+    let dy = diff(vector(0, DELTA, 0));
+    let dz = diff(vector(0, 0, DELTA));
+    vector(dx / DELTA, dy / DELTA, dz / DELTA)
+}
+
+/// Gradients transform like normals, but keep their length.
+fn covector_to_world(props: &ShapeProps, v: Vector) -> Vector {
+    let mut v = props.world_inverse_transpose().mul_vector(v);
+    v.set_w(0.0);
+    v
+}
+
+fn any_perpendicular(n: Vector) -> Vector {
+    let axis = if n.x().abs() < 0.9 {
+        vector(1, 0, 0)
+    } else {
+        vector(0, 1, 0)
+    };
+    n.cross(axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    fn ramp() -> GradientPattern {
+        gradient_pattern(color(0, 0, 0), color(1, 1, 1))
+    }
+
+    #[test]
+    fn a_bump_with_no_scale_leaves_the_normal_alone() {
+        let s = plane().with_normal_perturbation(noise_bump(noise(1)).with_scale(0));
+        assert_eq!(s.normal_at(point(0.3, 0, 0.7)), vector(0, 1, 0));
+    }
+
+    #[test]
+    fn a_bump_tilts_the_normal_down_the_slope() {
+        // the height rises by one unit for every unit along x
+        let s = plane().with_normal_perturbation(pattern_bump(ramp()));
+        let n = s.normal_at(point(0.5, 0, 0.5));
+        assert_eq!(n, vector(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0));
+    }
+
+    #[test]
+    fn a_large_step_in_a_bump_pattern_keeps_its_direction() {
+        // the height steps up from 0.1 to 0.9 at x = 1
+        let stripes = stripe_pattern(color(0.1, 0.1, 0.1), color(0.9, 0.9, 0.9));
+        let s = plane().with_normal_perturbation(pattern_bump(stripes));
+        let n = s.normal_at(point(1.0 - DELTA / 2.0, 0, 0.5));
+        assert!(n.x() < 0.0, "{n:?}");
+    }
+
+    #[test]
+    fn a_bump_follows_the_shapes_transform() {
+        let s = plane()
+            .with_transform(scaling(2, 1, 1))
+            .with_normal_perturbation(pattern_bump(ramp()));
+        let n = s.normal_at(point(1, 0, 0.5));
+        assert_eq!(n, vector(-0.5, 1, 0).normalize());
+    }
+
+    #[test]
+    fn a_bump_frequency_shrinks_the_height_field() {
+        let s = plane().with_normal_perturbation(pattern_bump(ramp()).with_frequency(2));
+        let n = s.normal_at(point(0.2, 0, 0.5));
+        assert_eq!(n, vector(-2, 1, 0).normalize());
+    }
+
+    #[test]
+    fn noise_bumps_vary_normals_across_a_surface() {
+        let s = sphere()
+            .with_normal_perturbation(noise_bump(noise(3)).with_scale(0.3).with_frequency(4));
+        let mut varied = false;
+        for i in 0..50 {
+            let theta = i as Num * 0.1;
+            let p = point(theta.cos(), theta.sin(), 0);
+            let geometric = vector(p.x(), p.y(), p.z());
+            let n = s.normal_at(p);
+            assert!(nums_equal(n.magnitude(), 1.0));
+            assert!(n.dot(geometric) > 0.0);
+            varied |= n != geometric;
+        }
+        assert!(varied);
+    }
+
+    #[test]
+    fn a_flat_normal_map_leaves_the_normal_alone() {
+        let mut image = canvas(1, 1);
+        image.write(0, 0, color(0.5, 0.5, 1));
+        let s =
+            plane().with_normal_perturbation(normal_map(image_texture(image), UvMapping::Planar));
+        assert_eq!(s.normal_at(point(0.3, 0, 0.7)), vector(0, 1, 0));
+    }
+
+    #[test]
+    fn a_normal_map_is_applied_in_tangent_space() {
+        let mut image = canvas(1, 1);
+        // pointing along u, which runs along x for a planar mapping
+        image.write(0, 0, color(1, 0.5, 0.5));
+        let s = plane()
+            .with_normal_perturbation(normal_map(image_texture(image.clone()), UvMapping::Planar));
+        assert_eq!(s.normal_at(point(0.3, 0, 0.7)), vector(1, 0, 0));
+        // and along v, which runs along z
+        image.write(0, 0, color(0.5, 1, 0.5));
+        let s =
+            plane().with_normal_perturbation(normal_map(image_texture(image), UvMapping::Planar));
+        assert_eq!(s.normal_at(point(0.3, 0, 0.7)), vector(0, 0, 1));
+    }
+
+    #[test]
+    fn a_normal_map_tangent_frame_follows_the_shape() {
+        let mut image = canvas(1, 1);
+        image.write(0, 0, color(1, 0.5, 0.5));
+        let s = plane()
+            .with_transform(rotation_y(std::f64::consts::FRAC_PI_2))
+            .with_normal_perturbation(normal_map(image_texture(image), UvMapping::Planar));
+        // object space x ends up pointing along world -z
+        assert_eq!(s.normal_at(point(0.3, 0, 0.7)), vector(0, 0, -1));
+    }
+
+    #[test]
+    fn normal_map_strength_blends_with_the_geometric_normal() {
+        let mut image = canvas(1, 1);
+        image.write(0, 0, color(1, 0.5, 0.5));
+        let s = plane().with_normal_perturbation(
+            normal_map(image_texture(image), UvMapping::Planar).with_strength(0.5),
+        );
+        assert_eq!(
+            s.normal_at(point(0.3, 0, 0.7)),
+            vector(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0)
+        );
+    }
+
+    #[test]
+    fn perturbed_normals_are_used_when_shading_a_hit() {
+        let s = plane().with_normal_perturbation(pattern_bump(ramp()));
+        let r = ray(point(0.5, 1, 0.5), vector(0, -1, 0));
+        let xs = s.intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        assert_eq!(comps.normalv, vector(-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0));
+    }
+}
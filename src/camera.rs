@@ -0,0 +1,176 @@
+use super::prelude::*;
+
+pub fn camera(hsize: usize, vsize: usize, field_of_view: impl Into<Num>) -> Camera {
+    Camera::new(hsize, vsize, field_of_view)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: Num,
+    transform: Matrix,
+    inverse: Matrix,
+    half_width: Num,
+    half_height: Num,
+    pixel_size: Num,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: impl Into<Num>) -> Self {
+        let field_of_view = field_of_view.into();
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as Num / vsize as Num;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+        let pixel_size = (half_width * 2.0) / hsize as Num;
+        Self {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: identity(),
+            inverse: identity(),
+            half_width,
+            half_height,
+            pixel_size,
+        }
+    }
+
+    pub fn with_transform(mut self, tf: Matrix) -> Self {
+        self.set_transform(tf);
+        self
+    }
+
+    pub fn set_transform(&mut self, tf: Matrix) {
+        self.transform = tf;
+        self.inverse = tf.inverse();
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> Num {
+        self.field_of_view
+    }
+
+    pub fn transform(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn pixel_size(&self) -> Num {
+        self.pixel_size
+    }
+
+    /// Builds the ray that starts at the camera and passes through the
+    /// center of the given pixel on the canvas.
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_for_offset(px, py, 0.0, 0.0)
+    }
+
+    /// Like `ray_for_pixel`, but passing through a point `dx` and `dy`
+    /// pixels to the right of and below the pixel's center.
+    pub fn ray_for_offset(&self, px: usize, py: usize, dx: Num, dy: Num) -> Ray {
+        // the offset from the edge of the canvas to the sample point
+        let xoffset = (px as Num + 0.5 + dx) * self.pixel_size;
+        let yoffset = (py as Num + 0.5 + dy) * self.pixel_size;
+        // the untransformed coordinates of the pixel in world space.
+        // the camera looks toward -z, so +x is to the left.
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+        // the canvas is at z=-1
+        let pixel = self.inverse.mul_point(point(world_x, world_y, -1));
+        let origin = self.inverse.mul_point(point(0, 0, 0));
+        let direction = (pixel - origin).normalize();
+        ray(origin, direction)
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = canvas(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                image.write(x, y, world.color_at(ray));
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+    #[test]
+    fn constructing_a_camera() {
+        let c = camera(160, 120, FRAC_PI_2);
+        assert_eq!(c.hsize, 160);
+        assert_eq!(c.vsize, 120);
+        assert_eq!(c.field_of_view, FRAC_PI_2);
+        assert_eq!(c.transform, identity());
+    }
+
+    #[test]
+    fn the_pixel_size_for_a_horizontal_canvas() {
+        let c = camera(200, 125, FRAC_PI_2);
+        assert!(nums_equal(c.pixel_size, 0.01));
+    }
+
+    #[test]
+    fn the_pixel_size_for_a_vertical_canvas() {
+        let c = camera(125, 200, FRAC_PI_2);
+        assert!(nums_equal(c.pixel_size, 0.01));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_the_center_of_the_canvas() {
+        let c = camera(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin(), point(0, 0, 0));
+        assert_eq!(r.dir(), vector(0, 0, -1));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_an_offset_in_a_pixel() {
+        let c = camera(201, 101, FRAC_PI_2);
+        assert_eq!(c.ray_for_offset(99, 49, 1.0, 1.0), c.ray_for_pixel(100, 50));
+        let r = c.ray_for_offset(0, 0, -0.5, -0.5);
+        assert_eq!(r.dir(), vector(1, 101.0 / 201.0, -1).normalize());
+    }
+
+    #[test]
+    fn constructing_a_ray_through_a_corner_of_the_canvas() {
+        let c = camera(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin(), point(0, 0, 0));
+        assert_eq!(r.dir(), vector(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn constructing_a_ray_when_the_camera_is_transformed() {
+        let c = camera(201, 101, FRAC_PI_2)
+            .with_transform(rotation_y(FRAC_PI_4).mul_matrix(translation(0, -2, 5)));
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin(), point(0, 2, -5));
+        assert_eq!(r.dir(), vector(FRAC_1_SQRT_2, 0, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn rendering_a_world_with_a_camera() {
+        let w = default_world();
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, FRAC_PI_2).with_transform(view_transform(from, to, up));
+        let image = c.render(&w);
+        assert_eq!(image.at(5, 5), color(0.38066, 0.47583, 0.2855));
+    }
+}
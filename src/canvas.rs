@@ -1,4 +1,8 @@
+use std::error;
+use std::fmt;
+use std::fs;
 use std::io;
+use std::path::Path;
 
 use crate::prelude::*;
 
@@ -6,6 +10,51 @@ pub fn canvas(width: usize, height: usize) -> Canvas {
     Canvas::new(width, height)
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanvasError {
+    /// A pixel outside of the canvas.
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A region that doesn't fit inside the canvas.
+    RegionOutOfBounds {
+        rect: Rect,
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "pixel ({x}, {y}) is outside of a {width}x{height} canvas"
+            ),
+            CanvasError::RegionOutOfBounds {
+                rect,
+                width,
+                height,
+            } => write!(
+                f,
+                "a {}x{} region at ({}, {}) is outside of a {width}x{height} canvas",
+                rect.width, rect.height, rect.x, rect.y
+            ),
+        }
+    }
+}
+
+impl error::Error for CanvasError {}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Canvas {
     pixels: Vec<Color>,
     pub width: usize,
@@ -25,15 +74,60 @@ impl Canvas {
         }
     }
 
+    /// Panics if the pixel is outside of the canvas. See `try_write`.
     pub fn write(&mut self, x: usize, y: usize, color: Color) {
-        let idx = self.idx(x, y);
-        let pixel = self.pixels.get_mut(idx).expect("invalid coords");
-        *pixel = color;
+        if let Err(err) = self.try_write(x, y, color) {
+            panic!("{err}");
+        }
     }
 
+    /// Panics if the pixel is outside of the canvas. See `get`.
     pub fn at(&self, x: usize, y: usize) -> Color {
-        let idx = self.idx(x, y);
-        *self.pixels.get(idx).expect("invalid coords")
+        self.get(x, y).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_write(&mut self, x: usize, y: usize, color: Color) -> Result<(), CanvasError> {
+        let idx = self.idx(x, y)?;
+        self.pixels[idx] = color;
+        Ok(())
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Result<Color, CanvasError> {
+        Ok(self.pixels[self.idx(x, y)?])
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    /// Every pixel with its coordinates, row by row from the top left.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        let width = self.width;
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(move |(i, &c)| (i % width, i / width, c))
+    }
+
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Color)> {
+        let width = self.width;
+        self.pixels
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, c)| (i % width, i / width, c))
+    }
+
+    /// The rows of the canvas, from the top.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[Color]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    pub fn rows_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut [Color]> {
+        self.pixels.chunks_mut(self.width.max(1))
     }
 
     pub fn ppm(&self) -> Vec<u8> {
@@ -44,7 +138,7 @@ impl Canvas {
         writeln!(buf, "P3");
         writeln!(buf, "{} {}", self.width, self.height);
         writeln!(buf, "{max_color}");
-        for row in self.pixels.chunks(self.width).map(|row| {
+        for row in self.rows().map(|row| {
             row.iter()
                 .flat_map(|c| [c.r(), c.g(), c.b()])
                 .map(|c| (c * max_color as Num).round() as usize)
@@ -70,8 +164,107 @@ impl Canvas {
         buf
     }
 
-    fn idx(&self, x: usize, y: usize) -> usize {
-        self.width * y + x
+    /// The same image as `ppm`, in the much smaller binary (P6) format.
+    pub fn ppm_binary(&self) -> Vec<u8> {
+        let max_color = 255;
+        let mut buf = format!("P6\n{} {}\n{max_color}\n", self.width, self.height).into_bytes();
+        buf.extend(
+            self.pixels
+                .iter()
+                .flat_map(|c| [c.r(), c.g(), c.b()])
+                .map(|c| (c * max_color as Num).round().clamp(0.0, max_color as Num) as u8),
+        );
+        buf
+    }
+
+    /// `ppm`, after putting every pixel through an output transform.
+    pub fn ppm_with(&self, transform: &OutputTransform) -> Vec<u8> {
+        self.transformed(transform).ppm()
+    }
+
+    /// `ppm_binary`, after putting every pixel through an output transform.
+    pub fn ppm_binary_with(&self, transform: &OutputTransform) -> Vec<u8> {
+        self.transformed(transform).ppm_binary()
+    }
+
+    /// A copy of the canvas with every pixel put through an output
+    /// transform, ready to be written out for display.
+    pub fn transformed(&self, transform: &OutputTransform) -> Canvas {
+        Canvas {
+            pixels: self.pixels.iter().map(|&c| transform.apply(c)).collect(),
+            ..*self
+        }
+    }
+
+    pub fn from_ppm_file(path: impl AsRef<Path>) -> Result<Self, PpmError> {
+        read_ppm_file(path)
+    }
+
+    /// Reads a plain (P3) or binary (P6) PPM image. Channels are scaled by
+    /// the maximum color value declared in the header, so they end up
+    /// between 0 and 1.
+    pub fn from_ppm(data: &[u8]) -> Result<Self, PpmError> {
+        parse_ppm(data)
+    }
+
+    /// An 8 bit PNG with no color chunks.
+    pub fn png(&self) -> Result<Vec<u8>, PngError> {
+        encode_png(self, &png_options())
+    }
+
+    pub fn png_with(&self, options: &PngOptions) -> Result<Vec<u8>, PngError> {
+        encode_png(self, options)
+    }
+
+    pub fn write_png_file(&self, path: impl AsRef<Path>) -> Result<(), PngError> {
+        Ok(fs::write(path, self.png()?)?)
+    }
+
+    pub fn from_png_file(path: impl AsRef<Path>) -> Result<Self, PngError> {
+        read_png_file(path)
+    }
+
+    pub fn from_png(data: &[u8]) -> Result<Self, PngError> {
+        decode_png(data)
+    }
+
+    /// A Portable Float Map, which keeps every channel as it is.
+    pub fn pfm(&self) -> Vec<u8> {
+        encode_pfm(self)
+    }
+
+    pub fn write_pfm_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.pfm())
+    }
+
+    /// An OpenEXR image, which keeps every channel as it is (to the
+    /// precision of a half float, by default).
+    pub fn exr(&self) -> Result<Vec<u8>, ExrError> {
+        encode_exr(self, &exr_options())
+    }
+
+    pub fn exr_with(&self, options: &ExrOptions) -> Result<Vec<u8>, ExrError> {
+        encode_exr(self, options)
+    }
+
+    pub fn write_exr_file(
+        &self,
+        path: impl AsRef<Path>,
+        options: &ExrOptions,
+    ) -> Result<(), ExrError> {
+        Ok(fs::write(path, self.exr_with(options)?)?)
+    }
+
+    fn idx(&self, x: usize, y: usize) -> Result<usize, CanvasError> {
+        if !self.contains(x, y) {
+            return Err(CanvasError::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(self.width * y + x)
     }
 }
 
@@ -98,6 +291,53 @@ mod tests {
         assert_eq!(c.at(2, 3), red);
     }
 
+    #[test]
+    fn test_writing_outside_the_canvas_is_an_error() {
+        let mut c = canvas(10, 20);
+        let err = c.try_write(10, 0, color(1, 0, 0)).unwrap_err();
+        assert_eq!(
+            err,
+            CanvasError::OutOfBounds {
+                x: 10,
+                y: 0,
+                width: 10,
+                height: 20
+            }
+        );
+        // which used to wrap around to the next row
+        assert_eq!(c.at(0, 1), color(0, 0, 0));
+        assert!(c.try_write(0, 20, color(1, 0, 0)).is_err());
+        assert!(c.get(3, 25).is_err());
+        c.try_write(9, 19, color(1, 0, 0)).unwrap();
+        assert_eq!(c.get(9, 19), Ok(color(1, 0, 0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel (10, 0) is outside of a 10x20 canvas")]
+    fn test_unchecked_writes_outside_the_canvas_panic() {
+        canvas(10, 20).write(10, 0, color(1, 0, 0));
+    }
+
+    #[test]
+    fn test_iterating_over_pixels_and_rows() {
+        let mut c = canvas(3, 2);
+        for (x, y, pixel) in c.pixels_mut() {
+            *pixel = color(x as Num, y as Num, 0);
+        }
+        let pixels = c.pixels().collect::<Vec<_>>();
+        assert_eq!(pixels.len(), 6);
+        assert_eq!(pixels[4], (1, 1, color(1, 1, 0)));
+        let rows = c.rows().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], [color(0, 1, 0), color(1, 1, 0), color(2, 1, 0)]);
+        for row in c.rows_mut() {
+            row[0] = color(9, 9, 9);
+        }
+        assert_eq!(c.at(0, 1), color(9, 9, 9));
+        c.fill(color(0.5, 0.5, 0.5));
+        assert!(c.pixels().all(|(_, _, p)| p == color(0.5, 0.5, 0.5)));
+    }
+
     #[test]
     fn test_constructing_ppm_header() {
         let c = canvas(5, 3);
@@ -153,6 +393,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_exporting_with_an_output_transform_leaves_the_canvas_alone() {
+        let mut c = canvas(2, 1);
+        c.write(0, 0, color(0.5, 1, 4));
+        c.write(1, 0, color(0.0031308, 0, 0.2));
+        let transform = output_transform()
+            .with_exposure(-1)
+            .with_tone_map(ToneMap::Reinhard)
+            .with_encoding(Encoding::Srgb);
+        let ppm = String::from_utf8(c.ppm_with(&transform)).unwrap();
+        let ppm = ppm.split('\n').collect::<Vec<_>>();
+        // 0.25 -> 0.2 -> 0.48453, 0.5 -> 0.33333 -> 0.61250, 2 -> 0.66667 -> 0.83601
+        assert_eq!(ppm[3], "124 156 213 5 0 85");
+        assert_eq!(c.at(0, 0), color(0.5, 1, 4));
+        assert_eq!(
+            c.ppm_binary_with(&transform)[11..],
+            [124, 156, 213, 5, 0, 85]
+        );
+    }
+
     #[test]
     fn test_ppm_files_terminated_by_newline() {
         let c = canvas(5, 3);
@@ -160,4 +420,95 @@ mod tests {
         let ppm = String::from_utf8(ppm).unwrap();
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn test_reading_a_file_with_the_wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+        assert!(Canvas::from_ppm(ppm.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_reading_a_ppm_returns_a_canvas_of_the_right_size() {
+        let ppm = format!("P3\n10 2\n255\n{}", "0 0 0\n".repeat(20));
+        let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        assert_eq!(c.width, 10);
+        assert_eq!(c.height, 2);
+    }
+
+    #[test]
+    fn test_reading_pixel_data_from_a_ppm_file() {
+        let ppm = "P3
+4 3
+255
+255 127 0  0 127 255  127 255 0  255 255 255
+0 0 0  255 0 0  0 255 0  0 0 255
+255 255 0  0 255 255  255 0 255  127 127 127
+";
+        let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        let cases = [
+            (0, 0, color(1, 0.49804, 0)),
+            (1, 0, color(0, 0.49804, 1)),
+            (2, 0, color(0.49804, 1, 0)),
+            (3, 0, color(1, 1, 1)),
+            (0, 1, color(0, 0, 0)),
+            (1, 1, color(1, 0, 0)),
+            (2, 1, color(0, 1, 0)),
+            (3, 1, color(0, 0, 1)),
+            (0, 2, color(1, 1, 0)),
+            (1, 2, color(0, 1, 1)),
+            (2, 2, color(1, 0, 1)),
+            (3, 2, color(0.49804, 0.49804, 0.49804)),
+        ];
+        for (x, y, expected) in cases {
+            assert_eq!(c.at(x, y), expected, "pixel at {x},{y}");
+        }
+    }
+
+    #[test]
+    fn test_ppm_parsing_ignores_comment_lines() {
+        let ppm = "P3
+# this is a comment
+2 1
+# this, too
+255
+# another comment
+255 255 255
+# oh, no, comments in the pixel data!
+255 0 255
+";
+        let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        assert_eq!(c.at(0, 0), color(1, 1, 1));
+        assert_eq!(c.at(1, 0), color(1, 0, 1));
+    }
+
+    #[test]
+    fn test_ppm_parsing_allows_an_rgb_triple_to_span_lines() {
+        let ppm = "P3\n1 1\n255\n51\n153\n\n204\n";
+        let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        assert_eq!(c.at(0, 0), color(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn test_ppm_parsing_respects_the_scale_setting() {
+        let ppm = "P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+        let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        assert_eq!(c.at(0, 1), color(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn test_reading_a_truncated_ppm_is_an_error() {
+        let ppm = "P3\n2 1\n255\n255 255 255 0 0\n";
+        assert!(Canvas::from_ppm(ppm.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut c = canvas(3, 2);
+        c.write(0, 0, color(1, 0, 0));
+        c.write(2, 1, color(0.2, 0.4, 0.6));
+        let read = Canvas::from_ppm(&c.ppm()).unwrap();
+        assert_eq!(read.at(0, 0), color(1, 0, 0));
+        assert_eq!(read.at(1, 0), color(0, 0, 0));
+        assert_eq!(read.at(2, 1), color(0.2, 0.4, 0.6));
+    }
 }
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn checker_pattern(a: impl IntoPattern, b: impl IntoPattern) -> CheckerPattern {
+    CheckerPattern::new(a, b)
+}
+
+/// Alternating unit cubes of `a` and `b`, like a three dimensional
+/// checkerboard.
+#[derive(Clone, Debug)]
+pub struct CheckerPattern {
+    props: PatternProps,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl CheckerPattern {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for CheckerPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let sum = point.x().floor() + point.y().floor() + point.z().floor();
+        if (sum as i64).rem_euclid(2) == 0 {
+            self.a.color_at(point)
+        } else {
+            self.b.color_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        color(1, 1, 1)
+    }
+
+    fn black() -> Color {
+        color(0, 0, 0)
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_x() {
+        let pattern = checker_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0.99, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(1.01, 0, 0)), black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_y() {
+        let pattern = checker_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 0.99, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 1.01, 0)), black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_z() {
+        let pattern = checker_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 0, 0.99)), white());
+        assert_eq!(pattern.color_at(point(0, 0, 1.01)), black());
+    }
+}
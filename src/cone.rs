@@ -0,0 +1,218 @@
+use super::prelude::*;
+
+pub fn cone() -> Cone {
+    Cone::default()
+}
+
+/// A double-napped cone around the y axis with its apex at the origin,
+/// optionally truncated at `minimum` and `maximum` and capped at both ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cone {
+    minimum: Num,
+    maximum: Num,
+    closed: bool,
+    props: ShapeProps,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            minimum: Num::NEG_INFINITY,
+            maximum: Num::INFINITY,
+            closed: false,
+            props: ShapeProps::default(),
+        }
+    }
+}
+
+impl Cone {
+    pub fn with_minimum(mut self, minimum: impl Into<Num>) -> Self {
+        self.minimum = minimum.into();
+        self
+    }
+
+    pub fn with_maximum(mut self, maximum: impl Into<Num>) -> Self {
+        self.maximum = maximum.into();
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn minimum(&self) -> Num {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> Num {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.dir().y().abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin().y()) / ray.dir().y();
+            // the radius of a cone's cap is the height at which it sits
+            if check_cap(ray, t, y.abs()) {
+                xs.push(intersection(t, self));
+            }
+        }
+    }
+}
+
+fn check_cap(ray: Ray, t: Num, radius: Num) -> bool {
+    let x = ray.origin().x() + t * ray.dir().x();
+    let z = ray.origin().z() + t * ray.dir().z();
+    (x * x + z * z) <= radius * radius
+}
+
+impl Shape for Cone {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let (o, d) = (ray.origin(), ray.dir());
+        let mut xs = vec![];
+        let a = d.x() * d.x() - d.y() * d.y() + d.z() * d.z();
+        let b = 2.0 * o.x() * d.x() - 2.0 * o.y() * d.y() + 2.0 * o.z() * d.z();
+        let c = o.x() * o.x() - o.y() * o.y() + o.z() * o.z();
+        let ts = if a.abs() < EPSILON {
+            // the ray is parallel to one of the cone's halves
+            if b.abs() < EPSILON {
+                vec![]
+            } else {
+                vec![-c / (2.0 * b)]
+            }
+        } else {
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return Intersections::default();
+            }
+            let t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                vec![t1, t0]
+            } else {
+                vec![t0, t1]
+            }
+        };
+        for t in ts {
+            let y = o.y() + t * d.y();
+            if self.minimum < y && y < self.maximum {
+                xs.push(intersection(t, self));
+            }
+        }
+        self.intersect_caps(ray, &mut xs);
+        intersections(xs)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        bounds(
+            point(-limit, self.minimum, -limit),
+            point(limit, self.maximum, limit),
+        )
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let dist = x * x + z * z;
+        if dist < y * y && y >= self.maximum - EPSILON {
+            vector(0, 1, 0)
+        } else if dist < y * y && y <= self.minimum + EPSILON {
+            vector(0, -1, 0)
+        } else {
+            let ny = dist.sqrt();
+            let ny = if y > 0.0 { -ny } else { ny };
+            vector(x, ny, z)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::SQRT_2;
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let shape = cone();
+        for (origin, direction, t0, t1) in [
+            (point(0, 0, -5), vector(0, 0, 1), 5.0, 5.0),
+            (point(0, 0, -5), vector(1, 1, 1), 8.66025, 8.66025),
+            (point(1, 1, -5), vector(-0.5, -1, 1), 4.55006, 49.44994),
+        ] {
+            let r = ray(origin, direction.normalize());
+            let xs = shape.local_intersect(r);
+            assert_eq!(xs.len(), 2, "{origin}");
+            assert!(nums_equal(xs[0].t(), t0), "{origin} {}", xs[0].t());
+            assert!(nums_equal(xs[1].t(), t1), "{origin} {}", xs[1].t());
+        }
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray_parallel_to_one_of_its_halves() {
+        let shape = cone();
+        let r = ray(point(0, 0, -1), vector(0, 1, 1).normalize());
+        let xs = shape.local_intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert!(nums_equal(xs[0].t(), 0.35355));
+    }
+
+    #[test]
+    fn intersecting_a_cones_end_caps() {
+        let shape = cone()
+            .with_minimum(-0.5)
+            .with_maximum(0.5)
+            .with_closed(true);
+        for (origin, direction, count) in [
+            (point(0, 0, -5), vector(0, 1, 0), 0),
+            (point(0, 0, -0.25), vector(0, 1, 1), 2),
+            (point(0, 0, -0.25), vector(0, 1, 0), 4),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert_eq!(shape.local_intersect(r).len(), count, "{origin}");
+        }
+    }
+
+    #[test]
+    fn computing_the_normal_vector_on_a_cone() {
+        let shape = cone();
+        for (p, normal) in [
+            (point(0, 0, 0), vector(0, 0, 0)),
+            (point(1, 1, 1), vector(1, -SQRT_2, 1)),
+            (point(-1, -1, 0), vector(-1, 1, 0)),
+        ] {
+            assert_eq!(shape.local_normal_at(p), normal, "{p}");
+        }
+    }
+
+    #[test]
+    fn an_unbounded_cone_has_a_bounding_box() {
+        let b = cone().local_bounds();
+        assert_eq!(
+            b.min(),
+            point(Num::NEG_INFINITY, Num::NEG_INFINITY, Num::NEG_INFINITY)
+        );
+        assert_eq!(b.max(), point(Num::INFINITY, Num::INFINITY, Num::INFINITY));
+    }
+
+    #[test]
+    fn a_bounded_cone_has_a_bounding_box() {
+        let b = cone().with_minimum(-5).with_maximum(3).local_bounds();
+        assert_eq!(b.min(), point(-5, -5, -5));
+        assert_eq!(b.max(), point(5, 3, 5));
+    }
+}
@@ -128,6 +128,13 @@ impl ops::Sub for Vector {
     }
 }
 
+impl ops::Neg for Vector {
+    type Output = Vector;
+    fn neg(self) -> Self::Output {
+        Vector { tup: -self.tup }
+    }
+}
+
 impl ops::Deref for Vector {
     type Target = Tuple4;
     fn deref(&self) -> &Self::Target {
@@ -182,6 +189,11 @@ impl Vector {
             self.x() * other.y() - self.y() * other.x(),
         )
     }
+    /// Reflects this vector around the normal, as a ball bouncing off a
+    /// surface would.
+    pub fn reflect(&self, normal: Vector) -> Vector {
+        *self - normal.mul_scalar(2.0 * self.dot(normal))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -215,10 +227,10 @@ impl ops::Neg for Tuple4 {
     type Output = Tuple4;
     fn neg(self) -> Self::Output {
         Tuple4([
-            -1.0 * self.x(),
-            -1.0 * self.y(),
-            -1.0 * self.z(),
-            -1.0 * self.w(),
+            -self.x(),
+            -self.y(),
+            -self.z(),
+            -self.w(),
         ])
     }
 }
@@ -302,9 +314,14 @@ impl PartialEq for Tuple4 {
     }
 }
 
+/// Tolerance used when comparing numbers and when nudging points off of a
+/// surface to avoid self-intersection.
+pub const EPSILON: Num = 0.00001;
+
 pub fn nums_equal(n1: impl Into<Num>, n2: impl Into<Num>) -> bool {
-    const EPSILON: f64 = 0.00001;
-    (n1.into() - n2.into()).abs() < EPSILON
+    let (n1, n2) = (n1.into(), n2.into());
+    // infinities are only equal to themselves
+    n1 == n2 || (n1 - n2).abs() < EPSILON
 }
 
 #[cfg(test)]
@@ -445,4 +462,22 @@ mod tests {
         assert_eq!(a.cross(b), vector(-1, 2, -1));
         assert_eq!(b.cross(a), vector(1, -2, 1));
     }
+
+    #[test]
+    fn test_reflecting_a_vector_approaching_at_45_degrees() {
+        let v = vector(1, -1, 0);
+        let n = vector(0, 1, 0);
+        assert_eq!(v.reflect(n), vector(1, 1, 0));
+    }
+
+    #[test]
+    fn test_reflecting_a_vector_off_a_slanted_surface() {
+        let v = vector(0, -1, 0);
+        let n = vector(
+            std::f64::consts::FRAC_1_SQRT_2,
+            std::f64::consts::FRAC_1_SQRT_2,
+            0,
+        );
+        assert_eq!(v.reflect(n), vector(1, 0, 0));
+    }
 }
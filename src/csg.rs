@@ -0,0 +1,333 @@
+use super::prelude::*;
+use crate::stats;
+
+pub fn csg(op: CsgOp, left: impl Shape + 'static, right: impl Shape + 'static) -> Csg {
+    Csg::new(op, Box::new(left), Box::new(right))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    /// Decides whether an intersection survives the operation, given which
+    /// side was hit and whether the hit is inside the left and right shapes.
+    pub fn intersection_allowed(self, lhit: bool, inl: bool, inr: bool) -> bool {
+        match self {
+            CsgOp::Union => (lhit && !inr) || (!lhit && !inl),
+            CsgOp::Intersection => (lhit && inr) || (!lhit && inl),
+            CsgOp::Difference => (lhit && !inr) || (!lhit && inl),
+        }
+    }
+}
+
+/// Constructive solid geometry: two shapes combined into one by keeping
+/// only the parts of their surfaces that the operation allows.
+#[derive(Debug)]
+pub struct Csg {
+    op: CsgOp,
+    props: ShapeProps,
+    children: [Box<dyn Shape>; 2],
+    bounds: Bounds,
+    primitives: usize,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Self {
+        let mut bounds = left.parent_space_bounds();
+        bounds.merge(right.parent_space_bounds());
+        let primitives = left.primitive_count() + right.primitive_count();
+        let mut csg = Self {
+            op,
+            props: ShapeProps::default(),
+            children: [left, right],
+            bounds,
+            primitives,
+        };
+        csg.propagate_transform();
+        csg
+    }
+
+    pub fn op(&self) -> CsgOp {
+        self.op
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.children[0].as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.children[1].as_ref()
+    }
+
+    pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Intersections<'a> {
+        let mut inl = false;
+        let mut inr = false;
+        xs.into_iter()
+            .filter(|i| {
+                let lhit = self.left().includes(i.object());
+                let allowed = self.op.intersection_allowed(lhit, inl, inr);
+                if lhit {
+                    inl = !inl;
+                } else {
+                    inr = !inr;
+                }
+                allowed
+            })
+            .collect()
+    }
+}
+
+impl Shape for Csg {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn propagate_transform(&mut self) {
+        let world = self.props.world_transform();
+        for child in self.children.iter_mut() {
+            child.set_parent_transform(world);
+        }
+    }
+
+    fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    fn primitive_count(&self) -> usize {
+        self.primitives
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        for child in self.children.iter_mut() {
+            child.divide(threshold);
+        }
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let hit = self.bounds.intersects(ray);
+        stats::record_bounds_test(hit, self.primitives);
+        if !hit {
+            return Intersections::default();
+        }
+        let xs = self
+            .children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .collect::<Intersections>()
+            .sorted();
+        self.filter_intersections(xs)
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("csg shapes have no surface; normals come from their children")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CsgOp::*;
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let c = csg(Union, sphere(), cube());
+        assert_eq!(c.op(), Union);
+        assert_eq!(c.children().len(), 2);
+        assert_eq!(c.left(), c.children()[0].as_ref());
+        assert_eq!(c.right(), c.children()[1].as_ref());
+        assert!(c.includes(c.left()));
+        assert!(c.includes(c.right()));
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        #[rustfmt::skip]
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+        for (op, lhit, inl, inr, result) in cases {
+            assert_eq!(
+                op.intersection_allowed(lhit, inl, inr),
+                result,
+                "{op:?} lhit={lhit} inl={inl} inr={inr}"
+            );
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        for (op, x0, x1) in [(Union, 0, 3), (Intersection, 1, 2), (Difference, 0, 1)] {
+            let c = csg(op, sphere(), cube());
+            let (s1, s2) = (c.left(), c.right());
+            let xs = intersections([
+                intersection(1, s1),
+                intersection(2, s2),
+                intersection(3, s1),
+                intersection(4, s2),
+            ]);
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(result.len(), 2, "{op:?}");
+            assert_eq!(result[0], xs[x0], "{op:?}");
+            assert_eq!(result[1], xs[x1], "{op:?}");
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_csg_object() {
+        let c = csg(Union, sphere(), cube());
+        let r = ray(point(0, 2, -5), vector(0, 0, 1));
+        assert!(c.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_hits_a_csg_object() {
+        let c = csg(
+            Union,
+            sphere(),
+            sphere().with_transform(translation(0, 0, 0.5)),
+        );
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = c.local_intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t(), 4.0);
+        assert_eq!(xs[0].object(), c.left());
+        assert_eq!(xs[1].t(), 6.5);
+        assert_eq!(xs[1].object(), c.right());
+        assert_eq!(xs.hit().map(|i| i.t()), Some(4.0));
+    }
+
+    #[test]
+    fn a_difference_carves_out_the_right_shape() {
+        // a unit cube with a sphere taken out of its front face
+        let c = csg(
+            Difference,
+            cube(),
+            sphere().with_transform(translation(0, 0, -1)),
+        );
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = c.intersect(r);
+        let hit = xs.hit().unwrap();
+        assert_eq!(hit.t(), 5.0);
+        assert_eq!(hit.object(), c.right());
+        // the normal points into the carved out hollow
+        let n = hit.prepare_computations(r, &xs).normalv;
+        assert_eq!(n, vector(0, 0, -1));
+    }
+
+    #[test]
+    fn csg_children_can_be_groups() {
+        let left = group().with_child(sphere());
+        let c = csg(
+            Intersection,
+            left,
+            cube().with_transform(translation(0, 0, 1)),
+        );
+        let inner = c.left().children()[0].as_ref();
+        assert!(c.includes(inner));
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = c.intersect(r);
+        assert_eq!(xs.iter().map(|i| i.t()).collect_vec(), vec![5.0, 6.0]);
+        assert_eq!(xs[0].object(), c.right());
+        assert_eq!(xs[1].object(), inner);
+    }
+
+    #[test]
+    fn csg_can_be_nested_in_a_transformed_group() {
+        let c = csg(
+            Union,
+            sphere(),
+            sphere().with_transform(translation(0, 0, 0.5)),
+        );
+        let g = group().with_transform(translation(0, 0, 1)).with_child(c);
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = g.intersect(r);
+        assert_eq!(xs.iter().map(|i| i.t()).collect_vec(), vec![5.0, 7.5]);
+        let inner = &g.children()[0].children()[0];
+        assert_eq!(inner.normal_at(point(0, 0, 0)), vector(0, 0, -1));
+    }
+
+    #[test]
+    fn a_csg_shape_has_a_bounding_box_that_contains_its_children() {
+        let c = csg(
+            Difference,
+            sphere(),
+            sphere().with_transform(translation(2, 3, 4)),
+        );
+        let b = c.local_bounds();
+        assert_eq!(b.min(), point(-1, -1, -1));
+        assert_eq!(b.max(), point(3, 4, 5));
+    }
+
+    #[test]
+    fn a_csg_shape_counts_the_primitives_on_both_sides() {
+        let inner = csg(Intersection, sphere(), cube());
+        let c = csg(
+            Difference,
+            inner,
+            group().with_child(sphere()).with_child(cube()),
+        );
+        assert_eq!(c.primitive_count(), 4);
+    }
+
+    #[test]
+    fn intersecting_ray_and_csg_doesnt_test_children_if_box_is_missed() {
+        let c = csg(Union, sphere(), cube());
+        let r = ray(point(0, 0, -5), vector(0, 1, 0));
+        take_stats();
+        assert!(c.intersect(r).is_empty());
+        let stats = take_stats();
+        assert_eq!(stats.primitive_tests, 0);
+        assert_eq!(stats.primitive_tests_saved, 2);
+    }
+
+    #[test]
+    fn subdividing_a_csg_shape_subdivides_its_children() {
+        let left = group()
+            .with_child(sphere().with_transform(translation(-1.5, 0, 0)))
+            .with_child(sphere().with_transform(translation(1.5, 0, 0)));
+        let right = group()
+            .with_child(sphere().with_transform(translation(0, 0, -1.5)))
+            .with_child(sphere().with_transform(translation(0, 0, 1.5)));
+        let mut c = csg(Difference, left, right);
+        c.divide(1);
+        for side in c.children() {
+            assert_eq!(side.children().len(), 2);
+            assert!(side.children().iter().all(|g| g.children().len() == 1));
+        }
+    }
+}
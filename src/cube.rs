@@ -0,0 +1,117 @@
+use super::prelude::*;
+use crate::bounds::check_axis;
+
+pub fn cube() -> Cube {
+    Cube::default()
+}
+
+/// An axis-aligned cube spanning -1 to 1 on every axis.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cube {
+    props: ShapeProps,
+}
+
+impl Shape for Cube {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let (xtmin, xtmax) = check_axis(ray.origin().x(), ray.dir().x(), -1.0, 1.0);
+        let (ytmin, ytmax) = check_axis(ray.origin().y(), ray.dir().y(), -1.0, 1.0);
+        let (ztmin, ztmax) = check_axis(ray.origin().z(), ray.dir().z(), -1.0, 1.0);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            return Intersections::default();
+        }
+        intersections([intersection(tmin, self), intersection(tmax, self)])
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        bounds(point(-1, -1, -1), point(1, 1, 1))
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let maxc = x.abs().max(y.abs()).max(z.abs());
+        if maxc == x.abs() {
+            vector(x, 0, 0)
+        } else if maxc == y.abs() {
+            vector(0, y, 0)
+        } else {
+            vector(0, 0, z)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_ray_intersects_a_cube() {
+        let c = cube();
+        for (origin, direction, t1, t2) in [
+            (point(5, 0.5, 0), vector(-1, 0, 0), 4.0, 6.0),
+            (point(-5, 0.5, 0), vector(1, 0, 0), 4.0, 6.0),
+            (point(0.5, 5, 0), vector(0, -1, 0), 4.0, 6.0),
+            (point(0.5, -5, 0), vector(0, 1, 0), 4.0, 6.0),
+            (point(0.5, 0, 5), vector(0, 0, -1), 4.0, 6.0),
+            (point(0.5, 0, -5), vector(0, 0, 1), 4.0, 6.0),
+            (point(0, 0.5, 0), vector(0, 0, 1), -1.0, 1.0),
+        ] {
+            let r = ray(origin, direction);
+            let xs = c.local_intersect(r);
+            assert_eq!(
+                xs.iter().map(|i| i.t()).collect_vec(),
+                vec![t1, t2],
+                "{origin} {direction:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_cube() {
+        let c = cube();
+        for (origin, direction) in [
+            (point(-2, 0, 0), vector(0.2673, 0.5345, 0.8018)),
+            (point(0, -2, 0), vector(0.8018, 0.2673, 0.5345)),
+            (point(0, 0, -2), vector(0.5345, 0.8018, 0.2673)),
+            (point(2, 0, 2), vector(0, 0, -1)),
+            (point(0, 2, 2), vector(0, -1, 0)),
+            (point(2, 2, 0), vector(-1, 0, 0)),
+        ] {
+            let r = ray(origin, direction);
+            assert!(c.local_intersect(r).is_empty(), "{origin} {direction:?}");
+        }
+    }
+
+    #[test]
+    fn the_normal_on_the_surface_of_a_cube() {
+        let c = cube();
+        for (p, normal) in [
+            (point(1, 0.5, -0.8), vector(1, 0, 0)),
+            (point(-1, -0.2, 0.9), vector(-1, 0, 0)),
+            (point(-0.4, 1, -0.1), vector(0, 1, 0)),
+            (point(0.3, -1, -0.7), vector(0, -1, 0)),
+            (point(-0.6, 0.3, 1), vector(0, 0, 1)),
+            (point(0.4, 0.4, -1), vector(0, 0, -1)),
+            (point(1, 1, 1), vector(1, 0, 0)),
+            (point(-1, -1, -1), vector(-1, 0, 0)),
+        ] {
+            assert_eq!(c.local_normal_at(p), normal, "{p}");
+        }
+    }
+
+    #[test]
+    fn a_cube_has_a_bounding_box() {
+        let b = cube().local_bounds();
+        assert_eq!(b.min(), point(-1, -1, -1));
+        assert_eq!(b.max(), point(1, 1, 1));
+    }
+}
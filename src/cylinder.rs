@@ -0,0 +1,245 @@
+use super::prelude::*;
+
+pub fn cylinder() -> Cylinder {
+    Cylinder::default()
+}
+
+/// A cylinder of radius 1 around the y axis, optionally truncated at
+/// `minimum` and `maximum` (exclusive) and capped at both ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cylinder {
+    minimum: Num,
+    maximum: Num,
+    closed: bool,
+    props: ShapeProps,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self {
+            minimum: Num::NEG_INFINITY,
+            maximum: Num::INFINITY,
+            closed: false,
+            props: ShapeProps::default(),
+        }
+    }
+}
+
+impl Cylinder {
+    pub fn with_minimum(mut self, minimum: impl Into<Num>) -> Self {
+        self.minimum = minimum.into();
+        self
+    }
+
+    pub fn with_maximum(mut self, maximum: impl Into<Num>) -> Self {
+        self.maximum = maximum.into();
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn minimum(&self) -> Num {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> Num {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.dir().y().abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin().y()) / ray.dir().y();
+            if check_cap(ray, t) {
+                xs.push(intersection(t, self));
+            }
+        }
+    }
+}
+
+/// Reports whether the intersection at t lies within the unit radius of a cap.
+fn check_cap(ray: Ray, t: Num) -> bool {
+    let x = ray.origin().x() + t * ray.dir().x();
+    let z = ray.origin().z() + t * ray.dir().z();
+    (x * x + z * z) <= 1.0
+}
+
+impl Shape for Cylinder {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let (o, d) = (ray.origin(), ray.dir());
+        let mut xs = vec![];
+        let a = d.x() * d.x() + d.z() * d.z();
+        // rays parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * o.x() * d.x() + 2.0 * o.z() * d.z();
+            let c = o.x() * o.x() + o.z() * o.z() - 1.0;
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return Intersections::default();
+            }
+            let t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let t1 = (-b + disc.sqrt()) / (2.0 * a);
+            let (t0, t1) = if t0 > t1 { (t1, t0) } else { (t0, t1) };
+            for t in [t0, t1] {
+                let y = o.y() + t * d.y();
+                if self.minimum < y && y < self.maximum {
+                    xs.push(intersection(t, self));
+                }
+            }
+        }
+        self.intersect_caps(ray, &mut xs);
+        intersections(xs)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        bounds(point(-1, self.minimum, -1), point(1, self.maximum, 1))
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.x() * point.x() + point.z() * point.z();
+        if dist < 1.0 && point.y() >= self.maximum - EPSILON {
+            vector(0, 1, 0)
+        } else if dist < 1.0 && point.y() <= self.minimum + EPSILON {
+            vector(0, -1, 0)
+        } else {
+            vector(point.x(), 0, point.z())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_ray_misses_a_cylinder() {
+        let cyl = cylinder();
+        for (origin, direction) in [
+            (point(1, 0, 0), vector(0, 1, 0)),
+            (point(0, 0, 0), vector(0, 1, 0)),
+            (point(0, 0, -5), vector(1, 1, 1)),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert!(cyl.local_intersect(r).is_empty(), "{origin}");
+        }
+    }
+
+    #[test]
+    fn a_ray_strikes_a_cylinder() {
+        let cyl = cylinder();
+        for (origin, direction, t0, t1) in [
+            (point(1, 0, -5), vector(0, 0, 1), 5.0, 5.0),
+            (point(0, 0, -5), vector(0, 0, 1), 4.0, 6.0),
+            (point(0.5, 0, -5), vector(0.1, 1, 1), 6.80798, 7.08872),
+        ] {
+            let r = ray(origin, direction.normalize());
+            let xs = cyl.local_intersect(r);
+            assert_eq!(xs.len(), 2, "{origin}");
+            assert!(nums_equal(xs[0].t(), t0), "{origin}");
+            assert!(nums_equal(xs[1].t(), t1), "{origin}");
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinder() {
+        let cyl = cylinder();
+        for (p, normal) in [
+            (point(1, 0, 0), vector(1, 0, 0)),
+            (point(0, 5, -1), vector(0, 0, -1)),
+            (point(0, -2, 1), vector(0, 0, 1)),
+            (point(-1, 1, 0), vector(-1, 0, 0)),
+        ] {
+            assert_eq!(cyl.local_normal_at(p), normal, "{p}");
+        }
+    }
+
+    #[test]
+    fn the_default_minimum_and_maximum_for_a_cylinder() {
+        let cyl = cylinder();
+        assert_eq!(cyl.minimum(), Num::NEG_INFINITY);
+        assert_eq!(cyl.maximum(), Num::INFINITY);
+    }
+
+    #[test]
+    fn intersecting_a_constrained_cylinder() {
+        let cyl = cylinder().with_minimum(1).with_maximum(2);
+        for (origin, direction, count) in [
+            (point(0, 1.5, 0), vector(0.1, 1, 0), 0),
+            (point(0, 3, -5), vector(0, 0, 1), 0),
+            (point(0, 0, -5), vector(0, 0, 1), 0),
+            (point(0, 2, -5), vector(0, 0, 1), 0),
+            (point(0, 1, -5), vector(0, 0, 1), 0),
+            (point(0, 1.5, -2), vector(0, 0, 1), 2),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(r).len(), count, "{origin}");
+        }
+    }
+
+    #[test]
+    fn the_default_closed_value_for_a_cylinder() {
+        let cyl = cylinder();
+        assert!(!cyl.closed());
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cylinder() {
+        let cyl = cylinder().with_minimum(1).with_maximum(2).with_closed(true);
+        for (origin, direction, count) in [
+            (point(0, 3, 0), vector(0, -1, 0), 2),
+            (point(0, 3, -2), vector(0, -1, 2), 2),
+            (point(0, 4, -2), vector(0, -1, 1), 2),
+            (point(0, 0, -2), vector(0, 1, 2), 2),
+            (point(0, -1, -2), vector(0, 1, 1), 2),
+        ] {
+            let r = ray(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(r).len(), count, "{origin}");
+        }
+    }
+
+    #[test]
+    fn the_normal_vector_on_a_cylinders_end_caps() {
+        let cyl = cylinder().with_minimum(1).with_maximum(2).with_closed(true);
+        for (p, normal) in [
+            (point(0, 1, 0), vector(0, -1, 0)),
+            (point(0.5, 1, 0), vector(0, -1, 0)),
+            (point(0, 1, 0.5), vector(0, -1, 0)),
+            (point(0, 2, 0), vector(0, 1, 0)),
+            (point(0.5, 2, 0), vector(0, 1, 0)),
+            (point(0, 2, 0.5), vector(0, 1, 0)),
+        ] {
+            assert_eq!(cyl.local_normal_at(p), normal, "{p}");
+        }
+    }
+
+    #[test]
+    fn an_unbounded_cylinder_has_a_bounding_box() {
+        let b = cylinder().local_bounds();
+        assert_eq!(b.min(), point(-1, Num::NEG_INFINITY, -1));
+        assert_eq!(b.max(), point(1, Num::INFINITY, 1));
+    }
+
+    #[test]
+    fn a_bounded_cylinder_has_a_bounding_box() {
+        let b = cylinder().with_minimum(-5).with_maximum(3).local_bounds();
+        assert_eq!(b.min(), point(-1, -5, -1));
+        assert_eq!(b.max(), point(1, 3, 1));
+    }
+}
@@ -0,0 +1,267 @@
+//! Simple 2D drawing on a canvas, for plots and debug overlays. Shapes are
+//! positioned in pixel coordinates, where (x, y) is the center of the pixel
+//! in column x and row y. Anything that falls outside of the canvas is
+//! clipped, and edges are anti-aliased by blending with what is already
+//! there.
+
+use super::prelude::*;
+
+impl Canvas {
+    /// Blends a color over a pixel: 0 leaves the pixel as it is, and 1
+    /// replaces it. Pixels outside of the canvas are ignored.
+    pub fn plot(&mut self, x: isize, y: isize, color: Color, alpha: impl Into<Num>) {
+        let alpha = alpha.into().clamp(0.0, 1.0);
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return;
+        };
+        if let Ok(existing) = self.get(x, y) {
+            self.write(x, y, existing + (color - existing).mul_scalar(alpha));
+        }
+    }
+
+    /// Fills a rectangle with a solid color, clipped to the canvas.
+    pub fn fill_rect(&mut self, area: Rect, color: Color) {
+        let area = area.intersect(&rect(0, 0, self.width, self.height));
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                self.write(x, y, color);
+            }
+        }
+    }
+
+    /// A one pixel wide, anti-aliased line, drawn with Xiaolin Wu's
+    /// algorithm.
+    pub fn draw_line(&mut self, from: (Num, Num), to: (Num, Num), color: Color) {
+        let ((mut x0, mut y0), (mut x1, mut y1)) = (from, to);
+        // walk along whichever axis the line changes most along, from left
+        // to right, so that there's exactly one step per column
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            (x0, y0, x1, y1) = (y0, x0, y1, x1);
+        }
+        if x0 > x1 {
+            (x0, y0, x1, y1) = (x1, y1, x0, y0);
+        }
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+        let columns = if steep { self.height } else { self.width } as isize;
+        let mut plot = |x: Num, y: Num, alpha: Num| {
+            let (x, y) = (x as isize, y as isize);
+            if steep {
+                self.plot(y, x, color, alpha);
+            } else {
+                self.plot(x, y, color, alpha);
+            }
+        };
+        // each end covers as much of its pixel as the line reaches into it
+        let mut end = |x: Num, gap: Num| {
+            let px = (x + 0.5).floor();
+            let y = y0 + gradient * (px - x0);
+            plot(px, y.floor(), (1.0 - y.rem_euclid(1.0)) * gap);
+            plot(px, y.floor() + 1.0, y.rem_euclid(1.0) * gap);
+            px
+        };
+        let first = end(x0, 1.0 - (x0 + 0.5).rem_euclid(1.0));
+        let last = end(x1, (x1 + 0.5).rem_euclid(1.0));
+        // only step through the columns that are on the canvas
+        let start = (first as isize).saturating_add(1).max(-1);
+        let stop = (last as isize).min(columns + 1);
+        for x in start..stop {
+            let x = x as Num;
+            let y = y0 + gradient * (x - x0);
+            plot(x, y.floor(), 1.0 - y.rem_euclid(1.0));
+            plot(x, y.floor() + 1.0, y.rem_euclid(1.0));
+        }
+    }
+
+    /// Lines joining each point to the next.
+    pub fn draw_polyline(&mut self, points: &[(Num, Num)], color: Color) {
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1], color);
+        }
+    }
+
+    /// The one pixel wide, anti-aliased outline of a circle.
+    pub fn draw_circle(&mut self, center: (Num, Num), radius: Num, color: Color) {
+        self.shade_circle(center, radius + 1.0, color, |d| 1.0 - (d - radius).abs());
+    }
+
+    /// A solid circle with anti-aliased edges.
+    pub fn fill_circle(&mut self, center: (Num, Num), radius: Num, color: Color) {
+        self.shade_circle(center, radius + 0.5, color, |d| radius + 0.5 - d);
+    }
+
+    /// Blends a color into every pixel within `reach` of the center, by the
+    /// coverage computed from the pixel's distance to the center.
+    fn shade_circle(
+        &mut self,
+        (cx, cy): (Num, Num),
+        reach: Num,
+        color: Color,
+        coverage: impl Fn(Num) -> Num,
+    ) {
+        // only visit the part of the circle's bounds that is on the canvas
+        let (width, height) = (self.width as isize, self.height as isize);
+        let left = ((cx - reach).floor() as isize).max(0);
+        let right = ((cx + reach).ceil() as isize).min(width - 1);
+        let top = ((cy - reach).floor() as isize).max(0);
+        let bottom = ((cy + reach).ceil() as isize).min(height - 1);
+        for y in top..=bottom {
+            for x in left..=right {
+                let d = (x as Num - cx).hypot(y as Num - cy);
+                let alpha = coverage(d);
+                if alpha > 0.0 {
+                    self.plot(x, y, color, alpha);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        color(1, 1, 1)
+    }
+
+    /// How much of each pixel in a row is covered.
+    fn row(c: &Canvas, y: usize) -> Vec<Num> {
+        (0..c.width).map(|x| c.at(x, y).r()).collect()
+    }
+
+    #[test]
+    fn plotting_blends_with_the_existing_color() {
+        let mut c = canvas(3, 3);
+        c.fill(color(0, 0, 1));
+        c.plot(1, 1, color(1, 0, 0), 0.25);
+        assert_eq!(c.at(1, 1), color(0.25, 0, 0.75));
+        c.plot(0, 0, color(1, 0, 0), 1);
+        assert_eq!(c.at(0, 0), color(1, 0, 0));
+        c.plot(2, 2, color(1, 0, 0), 0);
+        assert_eq!(c.at(2, 2), color(0, 0, 1));
+    }
+
+    #[test]
+    fn plotting_outside_the_canvas_does_nothing() {
+        let mut c = canvas(3, 3);
+        c.plot(-1, 0, white(), 1);
+        c.plot(0, 3, white(), 1);
+        c.plot(3, 0, white(), 1);
+        assert_eq!(c, canvas(3, 3));
+    }
+
+    #[test]
+    fn filling_a_rectangle_clips_it_to_the_canvas() {
+        let mut c = canvas(4, 3);
+        c.fill_rect(rect(2, 1, 10, 10), white());
+        assert_eq!(row(&c, 0), [0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(row(&c, 1), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(row(&c, 2), [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn a_horizontal_line_through_pixel_centers() {
+        let mut c = canvas(6, 3);
+        c.draw_line((1.0, 1.0), (4.0, 1.0), white());
+        // the ends stop at the pixel centers, so cover half of them
+        assert_eq!(row(&c, 1), [0.0, 0.5, 1.0, 1.0, 0.5, 0.0]);
+        assert_eq!(row(&c, 0), [0.0; 6]);
+        assert_eq!(row(&c, 2), [0.0; 6]);
+    }
+
+    #[test]
+    fn a_line_between_rows_is_shared_by_both() {
+        let mut c = canvas(6, 3);
+        c.draw_line((0.5, 0.5), (5.5, 0.5), white());
+        assert_eq!(row(&c, 0)[1..5], [0.5; 4]);
+        assert_eq!(row(&c, 1)[1..5], [0.5; 4]);
+        assert_eq!(row(&c, 2), [0.0; 6]);
+    }
+
+    #[test]
+    fn steep_lines_are_drawn_along_their_columns() {
+        let mut flat = canvas(7, 7);
+        flat.draw_line((0.0, 1.0), (6.0, 3.5), white());
+        let mut steep = canvas(7, 7);
+        steep.draw_line((1.0, 0.0), (3.5, 6.0), white());
+        for y in 0..7 {
+            for x in 0..7 {
+                assert_eq!(flat.at(x, y), steep.at(y, x), "at {x},{y}");
+            }
+        }
+        // every column in the middle of the line adds up to one pixel
+        for x in 1..6 {
+            let total: Num = (0..7).map(|y| flat.at(x, y).r()).sum();
+            assert!(nums_equal(total, 1.0), "column {x}");
+        }
+    }
+
+    #[test]
+    fn lines_can_be_drawn_in_either_direction() {
+        let mut a = canvas(8, 8);
+        a.draw_line((0.3, 1.2), (6.7, 5.1), white());
+        let mut b = canvas(8, 8);
+        b.draw_line((6.7, 5.1), (0.3, 1.2), white());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_canvas() {
+        let mut c = canvas(4, 4);
+        c.draw_line((-10.0, 2.0), (10.0, 2.0), white());
+        assert_eq!(row(&c, 2), [1.0; 4]);
+    }
+
+    #[test]
+    fn lines_far_off_the_canvas_are_clipped_before_they_are_drawn() {
+        let mut c = canvas(4, 4);
+        c.draw_line((1.0, 1.0), (1e12, 1.0), white());
+        assert_eq!(row(&c, 1), [0.0, 0.5, 1.0, 1.0]);
+        c.draw_line((-1e12, -1e12), (1e12, 1e12), white());
+        assert_eq!(c.at(3, 3), white());
+        // shapes that never reach the canvas leave it alone
+        let mut c = canvas(4, 4);
+        c.draw_line((-1e12, 1e9), (1e12, 1e9), white());
+        c.draw_circle((2.0, 2.0), 1e12, white());
+        c.fill_circle((1e12, 1e12), 1.0, white());
+        assert_eq!(c, canvas(4, 4));
+    }
+
+    #[test]
+    fn a_polyline_joins_its_points() {
+        let mut c = canvas(5, 5);
+        c.draw_polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)], white());
+        assert_eq!(c.at(2, 0), white());
+        assert_eq!(c.at(4, 2), white());
+        assert_eq!(c.at(2, 2), color(0, 0, 0));
+    }
+
+    #[test]
+    fn drawing_a_circle() {
+        let mut c = canvas(11, 11);
+        c.draw_circle((5.0, 5.0), 4.0, white());
+        for (x, y) in [(1, 5), (9, 5), (5, 1), (5, 9)] {
+            assert_eq!(c.at(x, y), white());
+        }
+        assert_eq!(c.at(5, 5), color(0, 0, 0));
+        assert_eq!(c.at(0, 0), color(0, 0, 0));
+        // a pixel the outline only passes near is partly covered
+        let partial = c.at(2, 2).r();
+        assert!(0.0 < partial && partial < 1.0);
+    }
+
+    #[test]
+    fn filling_a_circle() {
+        let mut c = canvas(11, 11);
+        c.fill_circle((5.0, 5.0), 3.0, white());
+        assert_eq!(c.at(5, 5), white());
+        assert_eq!(c.at(8, 5), color(0.5, 0.5, 0.5));
+        assert_eq!(c.at(9, 5), color(0, 0, 0));
+        assert_eq!(c.at(0, 0), color(0, 0, 0));
+        // circles hanging off the canvas are clipped
+        c.fill_circle((0.0, 0.0), 2.0, white());
+        assert_eq!(c.at(0, 0), white());
+    }
+}
@@ -0,0 +1,449 @@
+//! A writer for single part, scanline OpenEXR images with R, G and B
+//! channels, which keeps the full range of the canvas' colors.
+
+use std::{
+    error, fmt,
+    io::{self, Write},
+};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use super::prelude::*;
+
+pub fn exr_options() -> ExrOptions {
+    ExrOptions::default()
+}
+
+/// How each channel is stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExrPixelType {
+    /// 16 bit floats, which have about three significant digits and are
+    /// what most compositing tools expect.
+    #[default]
+    Half,
+    /// 32 bit floats.
+    Float,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExrCompression {
+    #[default]
+    None,
+    /// Lossless deflate compression of blocks of 16 scanlines.
+    Zip,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExrOptions {
+    pixel_type: ExrPixelType,
+    compression: ExrCompression,
+}
+
+impl ExrOptions {
+    pub fn with_pixel_type(mut self, pixel_type: ExrPixelType) -> Self {
+        self.pixel_type = pixel_type;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: ExrCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn pixel_type(&self) -> ExrPixelType {
+        self.pixel_type
+    }
+
+    pub fn compression(&self) -> ExrCompression {
+        self.compression
+    }
+
+    fn lines_per_block(&self) -> usize {
+        match self.compression {
+            ExrCompression::None => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExrError {
+    Io(io::Error),
+    /// EXR dimensions are limited to 32 bits, and can't be zero.
+    InvalidSize {
+        width: usize,
+        height: usize,
+    },
+    /// The size of each block is written as a 32 bit number.
+    BlockTooLarge {
+        y: usize,
+        size: usize,
+    },
+}
+
+impl fmt::Display for ExrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExrError::Io(err) => write!(f, "exr i/o error: {err}"),
+            ExrError::InvalidSize { width, height } => {
+                write!(f, "a {width}x{height} image can't be stored as an exr")
+            }
+            ExrError::BlockTooLarge { y, size } => {
+                write!(
+                    f,
+                    "the block at line {y} is too large for an exr ({size} bytes)"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for ExrError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ExrError::Io(err) => Some(err),
+            ExrError::InvalidSize { .. } | ExrError::BlockTooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExrError {
+    fn from(value: io::Error) -> Self {
+        ExrError::Io(value)
+    }
+}
+
+pub fn encode_exr(canvas: &Canvas, options: &ExrOptions) -> Result<Vec<u8>, ExrError> {
+    let (width, height) = (canvas.width, canvas.height);
+    let max = |n: usize| i32::try_from(n).ok().filter(|&n| n > 0).map(|n| n - 1);
+    let (Some(x_max), Some(y_max)) = (max(width), max(height)) else {
+        return Err(ExrError::InvalidSize { width, height });
+    };
+
+    let mut buf = vec![];
+    // magic number, then version 2 with no flags: a single part scanline
+    // image with short attribute names
+    buf.extend([0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+    let pixel_type: i32 = match options.pixel_type {
+        ExrPixelType::Half => 1,
+        ExrPixelType::Float => 2,
+    };
+    let mut channels = vec![];
+    // channels must be listed in alphabetical order
+    for name in [b"B", b"G", b"R"] {
+        channels.extend(name);
+        channels.push(0);
+        channels.extend(pixel_type.to_le_bytes());
+        // linear flag and three reserved bytes, then the sampling rates
+        channels.extend([0, 0, 0, 0]);
+        channels.extend(1i32.to_le_bytes());
+        channels.extend(1i32.to_le_bytes());
+    }
+    channels.push(0);
+    let window = [0, 0, x_max, y_max]
+        .into_iter()
+        .flat_map(i32::to_le_bytes)
+        .collect_vec();
+    let compression = match options.compression {
+        ExrCompression::None => 0,
+        ExrCompression::Zip => 3,
+    };
+    attribute(&mut buf, "channels", "chlist", &channels);
+    attribute(&mut buf, "compression", "compression", &[compression]);
+    attribute(&mut buf, "dataWindow", "box2i", &window);
+    attribute(&mut buf, "displayWindow", "box2i", &window);
+    attribute(&mut buf, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut buf, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut buf, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut buf, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    buf.push(0);
+
+    let lines = options.lines_per_block();
+    let blocks = (0..height)
+        .step_by(lines)
+        .map(|y| {
+            let data = block(canvas, y..(y + lines).min(height), options.pixel_type);
+            let data = match options.compression {
+                ExrCompression::None => data,
+                ExrCompression::Zip => zip(data)?,
+            };
+            Ok((y, data))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // the offset table points at each block from the start of the file
+    let mut offset = buf.len() + blocks.len() * 8;
+    for (_, data) in &blocks {
+        buf.extend((offset as u64).to_le_bytes());
+        offset += 8 + data.len();
+    }
+    for (y, data) in blocks {
+        // y is below the height, which is known to fit
+        let line = i32::try_from(y).map_err(|_| ExrError::InvalidSize { width, height })?;
+        let size = data.len();
+        let size = i32::try_from(size).map_err(|_| ExrError::BlockTooLarge { y, size })?;
+        buf.extend(line.to_le_bytes());
+        buf.extend(size.to_le_bytes());
+        buf.extend(data);
+    }
+    Ok(buf)
+}
+
+fn attribute(buf: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    buf.extend(name.as_bytes());
+    buf.push(0);
+    buf.extend(kind.as_bytes());
+    buf.push(0);
+    buf.extend((value.len() as i32).to_le_bytes());
+    buf.extend(value);
+}
+
+/// The samples for a run of scanlines: each line holds all of its blue
+/// samples, then the green, then the red.
+fn block(canvas: &Canvas, ys: std::ops::Range<usize>, pixel_type: ExrPixelType) -> Vec<u8> {
+    let mut data = vec![];
+    for y in ys {
+        for channel in [Color::b, Color::g, Color::r] {
+            for x in 0..canvas.width {
+                let v = channel(&canvas.at(x, y)) as f32;
+                match pixel_type {
+                    ExrPixelType::Half => data.extend(f32_to_half(v).to_le_bytes()),
+                    ExrPixelType::Float => data.extend(v.to_le_bytes()),
+                }
+            }
+        }
+    }
+    data
+}
+
+/// Compresses a block the way OpenEXR's ZIP compression does: the bytes
+/// are split into two halves (even and odd bytes), delta encoded, and then
+/// deflated. Blocks that don't get any smaller are stored as they are.
+fn zip(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let (even, odd): (Vec<_>, Vec<_>) = data.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+    let mut reordered = even.into_iter().chain(odd).map(|(_, &b)| b).collect_vec();
+    let mut prev = reordered.first().copied().unwrap_or_default();
+    for b in reordered.iter_mut().skip(1) {
+        let d = b.wrapping_sub(prev).wrapping_add(128);
+        prev = *b;
+        *b = d;
+    }
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(&reordered)?;
+    let compressed = encoder.finish()?;
+    Ok(if compressed.len() < data.len() {
+        compressed
+    } else {
+        data
+    })
+}
+
+/// Converts to a 16 bit float, rounding to the nearest representable value
+/// (ties to even). Values too large for a half become infinity.
+fn f32_to_half(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // infinity stays infinity, and NaN stays NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // a subnormal half, or zero if it's too small for even that
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        return sign | round_shift(mantissa, shift) as u16;
+    }
+    // rounding may carry into the exponent, which is what we want
+    let half = ((exponent as u32) << 10) + round_shift(mantissa, 13);
+    sign | half.min(0x7c00) as u16
+}
+
+/// Shifts right, rounding half to even.
+fn round_shift(v: u32, shift: u32) -> u32 {
+    let halfway = 1 << (shift - 1);
+    let remainder = v & ((1 << shift) - 1);
+    let shifted = v >> shift;
+    if remainder > halfway || (remainder == halfway && shifted & 1 == 1) {
+        shifted + 1
+    } else {
+        shifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    use super::*;
+
+    fn test_image() -> Canvas {
+        let mut c = canvas(5, 20);
+        for y in 0..20 {
+            for x in 0..5 {
+                c.write(x, y, color(x as Num * 0.25, y as Num * 0.5, 0.75));
+            }
+        }
+        c.write(0, 0, color(1.5, -0.5, 1000));
+        c
+    }
+
+    /// Reads back the pixels of an exr written by `encode_exr`, undoing the
+    /// ZIP compression if it's there.
+    fn decode(exr: &[u8], options: &ExrOptions, width: usize, height: usize) -> Canvas {
+        // skip over the attributes, which are a name, a type and a size
+        let mut header_end = 8;
+        while exr[header_end] != 0 {
+            for _ in 0..2 {
+                header_end += exr[header_end..].iter().position(|&b| b == 0).unwrap() + 1;
+            }
+            let size = i32::from_le_bytes(exr[header_end..header_end + 4].try_into().unwrap());
+            header_end += 4 + size as usize;
+        }
+        header_end += 1;
+        let lines = options.lines_per_block();
+        let blocks = height.div_ceil(lines);
+        let read_u64 = |at: usize| u64::from_le_bytes(exr[at..at + 8].try_into().unwrap());
+        let read_i32 = |at: usize| i32::from_le_bytes(exr[at..at + 4].try_into().unwrap());
+        let size = match options.pixel_type {
+            ExrPixelType::Half => 2,
+            ExrPixelType::Float => 4,
+        };
+        let mut c = canvas(width, height);
+        for i in 0..blocks {
+            let offset = read_u64(header_end + i * 8) as usize;
+            let y = read_i32(offset) as usize;
+            let len = read_i32(offset + 4) as usize;
+            let mut data = exr[offset + 8..offset + 8 + len].to_vec();
+            let count = (height - y).min(lines);
+            let expected = count * width * 3 * size;
+            if data.len() < expected {
+                let mut raw = vec![];
+                ZlibDecoder::new(&data[..]).read_to_end(&mut raw).unwrap();
+                for i in 1..raw.len() {
+                    raw[i] = raw[i - 1].wrapping_add(raw[i]).wrapping_sub(128);
+                }
+                let (even, odd) = raw.split_at(raw.len().div_ceil(2));
+                data = (0..raw.len())
+                    .map(|i| if i % 2 == 0 { even[i / 2] } else { odd[i / 2] })
+                    .collect();
+            }
+            let samples = data
+                .chunks_exact(size)
+                .map(|b| match size {
+                    2 => half_to_f32(u16::from_le_bytes([b[0], b[1]])),
+                    _ => f32::from_le_bytes(b.try_into().unwrap()),
+                } as Num)
+                .collect_vec();
+            for (line, samples) in samples.chunks_exact(width * 3).enumerate() {
+                let (b, rest) = samples.split_at(width);
+                let (g, r) = rest.split_at(width);
+                for x in 0..width {
+                    c.write(x, y + line, color(r[x], g[x], b[x]));
+                }
+            }
+        }
+        c
+    }
+
+    fn half_to_f32(h: u16) -> f32 {
+        let sign = if h & 0x8000 == 0 { 1.0 } else { -1.0 };
+        let exponent = ((h >> 10) & 0x1f) as i32;
+        let mantissa = (h & 0x3ff) as f32;
+        sign * match exponent {
+            0 => mantissa * 2f32.powi(-24),
+            0x1f if mantissa == 0.0 => f32::INFINITY,
+            0x1f => f32::NAN,
+            _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    #[test]
+    fn converting_floats_to_halves() {
+        let cases = [
+            (0.0, 0x0000),
+            (-0.0, 0x8000),
+            (1.0, 0x3c00),
+            (-2.0, 0xc000),
+            (0.5, 0x3800),
+            (65504.0, 0x7bff),
+            (65520.0, 0x7c00),
+            (1e10, 0x7c00),
+            (f32::INFINITY, 0x7c00),
+            (2f32.powi(-24), 0x0001),
+            (2f32.powi(-14), 0x0400),
+            (1e-10, 0x0000),
+            // halfway between 1 and the next half rounds to even
+            (1.0 + 2f32.powi(-11), 0x3c00),
+            (1.0 + 3.0 * 2f32.powi(-11), 0x3c02),
+        ];
+        for (v, expected) in cases {
+            assert_eq!(f32_to_half(v), expected, "converting {v}");
+        }
+        assert!(half_to_f32(f32_to_half(f32::NAN)).is_nan());
+    }
+
+    #[test]
+    fn an_exr_starts_with_the_magic_number_and_header() {
+        let exr = encode_exr(&test_image(), &exr_options()).unwrap();
+        assert_eq!(exr[..8], [0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        assert_eq!(&exr[8..26], b"channels\0chlist\0\x37\0");
+        for name in ["compression", "dataWindow", "displayWindow", "lineOrder"] {
+            let found = exr.windows(name.len()).any(|w| w == name.as_bytes());
+            assert!(found, "missing {name}");
+        }
+    }
+
+    #[test]
+    fn every_combination_of_options_keeps_the_pixels() {
+        let c = test_image();
+        for pixel_type in [ExrPixelType::Half, ExrPixelType::Float] {
+            for compression in [ExrCompression::None, ExrCompression::Zip] {
+                let options = exr_options()
+                    .with_pixel_type(pixel_type)
+                    .with_compression(compression);
+                let exr = c.exr_with(&options).unwrap();
+                // every value in the test image is exactly representable
+                assert_eq!(decode(&exr, &options, 5, 20), c, "{options:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn zip_compression_makes_smooth_images_smaller() {
+        let c = test_image();
+        let plain = encode_exr(&c, &exr_options()).unwrap();
+        let zipped = encode_exr(&c, &exr_options().with_compression(ExrCompression::Zip)).unwrap();
+        assert!(zipped.len() < plain.len());
+    }
+
+    #[test]
+    fn an_empty_canvas_cannot_be_an_exr() {
+        let err = encode_exr(&canvas(0, 0), &exr_options()).unwrap_err();
+        assert!(matches!(
+            err,
+            ExrError::InvalidSize {
+                width: 0,
+                height: 0
+            }
+        ));
+        assert_eq!(err.to_string(), "a 0x0 image can't be stored as an exr");
+    }
+
+    #[test]
+    fn exr_uses_the_default_options() {
+        let c = test_image();
+        assert_eq!(c.exr().unwrap(), c.exr_with(&exr_options()).unwrap());
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn gradient_pattern(a: impl IntoPattern, b: impl IntoPattern) -> GradientPattern {
+    GradientPattern::new(a, b)
+}
+
+/// Blends linearly from `a` to `b` along x, starting over every unit.
+#[derive(Clone, Debug)]
+pub struct GradientPattern {
+    props: PatternProps,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl GradientPattern {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for GradientPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let a = self.a.color_at(point);
+        let b = self.b.color_at(point);
+        let fraction = point.x() - point.x().floor();
+        a + (b - a).mul_scalar(fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_gradient_linearly_interpolates_between_colors() {
+        let pattern = gradient_pattern(color(1, 1, 1), color(0, 0, 0));
+        assert_eq!(pattern.color_at(point(0, 0, 0)), color(1, 1, 1));
+        assert_eq!(pattern.color_at(point(0.25, 0, 0)), color(0.75, 0.75, 0.75));
+        assert_eq!(pattern.color_at(point(0.5, 0, 0)), color(0.5, 0.5, 0.5));
+        assert_eq!(pattern.color_at(point(0.75, 0, 0)), color(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn a_gradient_repeats_every_unit() {
+        let pattern = gradient_pattern(color(1, 1, 1), color(0, 0, 0));
+        assert_eq!(pattern.color_at(point(1.25, 0, 0)), color(0.75, 0.75, 0.75));
+        assert_eq!(
+            pattern.color_at(point(-0.75, 0, 0)),
+            color(0.75, 0.75, 0.75)
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use super::prelude::*;
+use crate::stats;
+
+pub fn group() -> Group {
+    Group::default()
+}
+
+type Children = Vec<Box<dyn Shape>>;
+
+/// The most children a group directly holds when a bounding volume
+/// hierarchy is built without asking for a particular threshold.
+pub const DEFAULT_DIVIDE_THRESHOLD: usize = 8;
+
+/// A collection of shapes that are transformed as a unit. The group's
+/// transform applies on top of each child's own transform.
+///
+/// The box around the children is cached so that rays which miss it skip
+/// the children entirely, and `divide` nests the children into subgroups to
+/// form a bounding volume hierarchy.
+#[derive(Debug, Default)]
+pub struct Group {
+    props: ShapeProps,
+    children: Vec<Box<dyn Shape>>,
+    bounds: Bounds,
+    primitives: usize,
+}
+
+impl Group {
+    pub fn with_child(mut self, child: impl Shape + 'static) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    pub fn add_child(&mut self, child: impl Shape + 'static) {
+        self.add_boxed_child(Box::new(child));
+    }
+
+    pub fn add_boxed_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent_transform(self.props.world_transform());
+        self.bounds.merge(child.parent_space_bounds());
+        self.primitives += child.primitive_count();
+        self.children.push(child);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Splits the children at the median of their centers along the axis
+    /// where the centers are most spread out. Children without finite
+    /// bounds can't be placed and stay where they are.
+    fn partition_children(&mut self) -> (Children, Children) {
+        let (mut bounded, unbounded): (Vec<_>, Vec<_>) = std::mem::take(&mut self.children)
+            .into_iter()
+            .partition(|c| c.parent_space_bounds().is_finite());
+        self.children = unbounded;
+        let centroids = bounded
+            .iter()
+            .map(|c| c.parent_space_bounds().centroid())
+            .fold(Bounds::empty(), Bounds::with_point);
+        let axis = centroids.longest_axis();
+        let key = |c: &dyn Shape| c.parent_space_bounds().centroid().get(axis);
+        bounded.sort_by(|a, b| key(a.as_ref()).total_cmp(&key(b.as_ref())));
+        let right = bounded.split_off(bounded.len() / 2);
+        (bounded, right)
+    }
+
+    fn add_subgroup(&mut self, children: Children) {
+        let mut sub = group();
+        sub.set_parent_transform(self.props.world_transform());
+        for child in children {
+            sub.add_boxed_child(child);
+        }
+        self.children.push(Box::new(sub));
+    }
+}
+
+impl Shape for Group {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn propagate_transform(&mut self) {
+        let world = self.props.world_transform();
+        for child in self.children.iter_mut() {
+            child.set_parent_transform(world);
+        }
+    }
+
+    fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    fn primitive_count(&self) -> usize {
+        self.primitives
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        let threshold = threshold.max(1);
+        if self.children.len() > threshold {
+            let (left, right) = self.partition_children();
+            if left.is_empty() || right.is_empty() {
+                self.children.extend(left.into_iter().chain(right));
+            } else {
+                self.add_subgroup(left);
+                self.add_subgroup(right);
+            }
+        }
+        for child in self.children.iter_mut() {
+            child.divide(threshold);
+        }
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let hit = self.bounds.intersects(ray);
+        stats::record_bounds_test(hit, self.primitives);
+        if !hit {
+            return Intersections::default();
+        }
+        self.children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .collect::<Intersections>()
+            .sorted()
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("groups have no surface; normals come from their children")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::tests::test_shape;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = group();
+        assert_eq!(g.transform(), identity());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let g = group()
+            .with_transform(translation(1, 2, 3))
+            .with_child(test_shape());
+        assert!(!g.is_empty());
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(
+            g.children()[0].props().parent_transform(),
+            translation(1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn changing_a_groups_transform_updates_its_children() {
+        let mut g = group().with_child(group().with_child(test_shape()));
+        g.set_transform(scaling(2, 2, 2));
+        let grandchild = &g.children()[0].children()[0];
+        assert_eq!(grandchild.props().parent_transform(), scaling(2, 2, 2));
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = group();
+        let r = ray(point(0, 0, 0), vector(0, 0, 1));
+        assert!(g.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let g = group()
+            .with_child(sphere())
+            .with_child(sphere().with_transform(translation(0, 0, -3)))
+            .with_child(sphere().with_transform(translation(5, 0, 0)));
+        let (s1, s2) = (g.children()[0].as_ref(), g.children()[1].as_ref());
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = g.local_intersect(r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].object(), s2);
+        assert_eq!(xs[1].object(), s2);
+        assert_eq!(xs[2].object(), s1);
+        assert_eq!(xs[3].object(), s1);
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let g = group()
+            .with_transform(scaling(2, 2, 2))
+            .with_child(sphere().with_transform(translation(5, 0, 0)));
+        let r = ray(point(10, 0, -10), vector(0, 0, 1));
+        let xs = g.intersect(r);
+        assert_eq!(xs.len(), 2);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let g2 = group()
+            .with_transform(scaling(2, 2, 2))
+            .with_child(sphere().with_transform(translation(5, 0, 0)));
+        let g1 = group().with_transform(rotation_y(FRAC_PI_2)).with_child(g2);
+        let s = &g1.children()[0].children()[0];
+        let p = s.world_to_object(point(-2, 0, -10));
+        assert_eq!(p, point(0, 0, -1));
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let g2 = group()
+            .with_transform(scaling(1, 2, 3))
+            .with_child(sphere().with_transform(translation(5, 0, 0)));
+        let g1 = group().with_transform(rotation_y(FRAC_PI_2)).with_child(g2);
+        let s = &g1.children()[0].children()[0];
+        let v = 3.0_f64.sqrt() / 3.0;
+        let n = s.normal_to_world(vector(v, v, v));
+        assert_eq!(n, vector(0.28571, 0.42857, -0.85714));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let g2 = group()
+            .with_transform(scaling(1, 2, 3))
+            .with_child(sphere().with_transform(translation(5, 0, 0)));
+        let g1 = group().with_transform(rotation_y(FRAC_PI_2)).with_child(g2);
+        let s = &g1.children()[0].children()[0];
+        let n = s.normal_at(point(1.7321, 1.1547, -5.5774));
+        assert_eq!(n, vector(0.28570, 0.42854, -0.85716));
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let s = sphere().with_transform(translation(2, 5, -3).mul_matrix(scaling(2, 2, 2)));
+        let c = cylinder()
+            .with_minimum(-2)
+            .with_maximum(2)
+            .with_transform(translation(-4, -1, 4).mul_matrix(scaling(0.5, 1, 0.5)));
+        let g = group().with_child(s).with_child(c);
+        let b = g.local_bounds();
+        assert_eq!(b.min(), point(-4.5, -3, -5));
+        assert_eq!(b.max(), point(4, 7, 4.5));
+    }
+
+    #[test]
+    fn intersecting_ray_and_group_doesnt_test_children_if_box_is_missed() {
+        let g = group().with_child(test_shape());
+        let r = ray(point(0, 0, -5), vector(0, 1, 0));
+        take_stats();
+        g.intersect(r);
+        let stats = take_stats();
+        assert_eq!(stats.bounds_misses, 1);
+        assert_eq!(stats.primitive_tests, 0);
+        assert_eq!(stats.primitive_tests_saved, 1);
+    }
+
+    #[test]
+    fn intersecting_ray_and_group_tests_children_if_box_is_hit() {
+        let g = group().with_child(test_shape());
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        take_stats();
+        g.intersect(r);
+        let stats = take_stats();
+        assert_eq!(stats.bounds_misses, 0);
+        assert_eq!(stats.primitive_tests, 1);
+    }
+
+    #[test]
+    fn partitioning_a_groups_children() {
+        let mut g = group()
+            .with_child(sphere().with_transform(translation(-2, 0, 0)))
+            .with_child(sphere().with_transform(translation(2, 0, 0)))
+            .with_child(sphere());
+        let (left, right) = g.partition_children();
+        assert!(g.is_empty());
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 2);
+        assert_eq!(left[0].transform(), translation(-2, 0, 0));
+        assert_eq!(right[0].transform(), identity());
+        assert_eq!(right[1].transform(), translation(2, 0, 0));
+    }
+
+    #[test]
+    fn partitioning_leaves_unbounded_children_in_place() {
+        let mut g = group()
+            .with_child(plane())
+            .with_child(sphere().with_transform(translation(-2, 0, 0)))
+            .with_child(sphere().with_transform(translation(2, 0, 0)));
+        let (left, right) = g.partition_children();
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+    }
+
+    #[test]
+    fn subdividing_a_group_partitions_its_children() {
+        let mut g = group()
+            .with_child(sphere().with_transform(translation(-2, -2, 0)))
+            .with_child(sphere().with_transform(translation(-2, 2, 0)))
+            .with_child(sphere().with_transform(scaling(4, 4, 4)));
+        g.divide(1);
+        assert_eq!(g.children().len(), 2);
+        assert_eq!(g.primitive_count(), 3);
+        let left = &g.children()[0];
+        assert_eq!(left.children().len(), 1);
+        assert_eq!(left.children()[0].transform(), translation(-2, -2, 0));
+        let right = &g.children()[1];
+        assert_eq!(right.children().len(), 2);
+        for sub in right.children() {
+            assert_eq!(sub.children().len(), 1);
+        }
+    }
+
+    #[test]
+    fn subdividing_a_group_with_too_few_children() {
+        let mut g = group()
+            .with_child(sphere().with_transform(translation(-2, 0, 0)))
+            .with_child(sphere().with_transform(translation(2, 1, 0)))
+            .with_child(sphere().with_transform(translation(2, -1, 0)));
+        g.divide(3);
+        assert_eq!(g.children().len(), 3);
+        assert!(g.children().iter().all(|c| c.children().is_empty()));
+    }
+
+    #[test]
+    fn subdividing_a_transformed_group_keeps_world_positions() {
+        let mut g = group().with_transform(translation(0, 0, 10));
+        for x in -5..5 {
+            g.add_child(sphere().with_transform(translation(x * 3, 0, 0)));
+        }
+        let r = ray(point(3, 0, -5), vector(0, 0, 1));
+        let before = g.intersect(r).iter().map(|i| i.t()).collect_vec();
+        g.divide(2);
+        let after = g.intersect(r).iter().map(|i| i.t()).collect_vec();
+        assert_eq!(before, vec![14.0, 16.0]);
+        assert_eq!(before, after);
+        let hit = g.intersect(r).hit().unwrap();
+        assert_eq!(hit.object().normal_at(point(3, 0, 9)), vector(0, 0, -1));
+    }
+
+    #[test]
+    fn dividing_a_group_saves_primitive_tests() {
+        let mut g = group();
+        for x in 0..64 {
+            g.add_child(sphere().with_transform(translation(x * 3, 0, 0)));
+        }
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        take_stats();
+        g.intersect(r);
+        let flat = take_stats();
+        assert_eq!(flat.primitive_tests, 64);
+        g.divide(4);
+        g.intersect(r);
+        let divided = take_stats();
+        assert!(divided.primitive_tests <= 4, "{divided}");
+        assert_eq!(divided.primitive_tests + divided.primitive_tests_saved, 64);
+    }
+}
@@ -1,18 +1,23 @@
 use super::prelude::*;
 
-pub fn intersection(t: impl Into<Num>, object: impl Into<Intersected>) -> Intersection {
+pub fn intersection(t: impl Into<Num>, object: &dyn Shape) -> Intersection<'_> {
     Intersection::new(t, object)
 }
 
-pub fn intersections(xs: impl IntoIterator<Item = Intersection>) -> Intersections {
+pub fn intersections<'a>(xs: impl IntoIterator<Item = Intersection<'a>>) -> Intersections<'a> {
     Intersections(xs.into_iter().collect())
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
-pub struct Intersections(Vec<Intersection>);
+pub struct Intersections<'a>(Vec<Intersection<'a>>);
 
-impl Intersections {
-    pub fn hit(&self) -> Option<Intersection> {
+impl<'a> Intersections<'a> {
+    pub fn sorted(mut self) -> Self {
+        self.0.sort_by(|a, b| a.t.total_cmp(&b.t));
+        self
+    }
+
+    pub fn hit(&self) -> Option<Intersection<'a>> {
         self.0
             .iter()
             .filter(|i| i.t > 0.0)
@@ -24,67 +29,192 @@ impl Intersections {
     }
 }
 
-impl std::ops::Deref for Intersections {
-    type Target = Vec<Intersection>;
+impl<'a> FromIterator<Intersection<'a>> for Intersections<'a> {
+    fn from_iter<T: IntoIterator<Item = Intersection<'a>>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+    type Item = Intersection<'a>;
+    type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> std::ops::Deref for Intersections<'a> {
+    type Target = Vec<Intersection<'a>>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
+/// A point along a ray where it crosses the surface of an object. The
+/// object is borrowed rather than copied, so intersections are cheap to
+/// pass around and identify the exact shape that was hit.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Intersection {
+pub struct Intersection<'a> {
     t: Num,
-    object: Intersected,
+    object: &'a dyn Shape,
+    u: Num,
+    v: Num,
 }
 
-impl Intersection {
-    pub fn new(t: impl Into<Num>, object: impl Into<Intersected>) -> Self {
+impl<'a> Intersection<'a> {
+    pub fn new(t: impl Into<Num>, object: &'a dyn Shape) -> Self {
         Self {
             t: t.into(),
-            object: object.into(),
+            object,
+            u: 0.0,
+            v: 0.0,
         }
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Intersected {
-    Sphere(Sphere),
-}
+    /// Records where on a triangle the intersection occurred.
+    pub fn with_uv(mut self, u: impl Into<Num>, v: impl Into<Num>) -> Self {
+        self.u = u.into();
+        self.v = v.into();
+        self
+    }
+
+    pub fn t(&self) -> Num {
+        self.t
+    }
+
+    pub fn u(&self) -> Num {
+        self.u
+    }
+
+    pub fn v(&self) -> Num {
+        self.v
+    }
+
+    pub fn object(&self) -> &'a dyn Shape {
+        self.object
+    }
 
-impl PartialEq<Sphere> for Intersected {
-    #[allow(irrefutable_let_patterns)]
-    fn eq(&self, other: &Sphere) -> bool {
-        if let Intersected::Sphere(s) = self {
-            s == other
-        } else {
-            false
+    /// Precomputes the values needed to shade this intersection. `xs` is
+    /// the full list of intersections the hit came from, which is needed
+    /// to work out which materials the ray is passing between.
+    pub fn prepare_computations(&self, ray: Ray, xs: &Intersections<'a>) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.dir();
+        let mut normalv = self.object.normal_at_hit(point, self);
+        let inside = normalv.dot(eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+        // nudged slightly above the surface so that shadow rays don't
+        // intersect the object they start from (shadow acne)
+        let over_point = point.add_vector(normalv.mul_scalar(EPSILON));
+        // and slightly below for refracted rays, which start inside it
+        let under_point = point.sub_vector(normalv.mul_scalar(EPSILON));
+        let reflectv = ray.dir().reflect(normalv);
+        let (n1, n2) = self.refractive_indices(xs);
+        Computations {
+            t: self.t,
+            object: self.object,
+            point,
+            over_point,
+            under_point,
+            eyev,
+            normalv,
+            reflectv,
+            inside,
+            n1,
+            n2,
         }
     }
+
+    /// The refractive indices of the materials on either side of this
+    /// intersection, found by walking the intersections up to it and
+    /// tracking which objects the ray is currently inside of.
+    fn refractive_indices(&self, xs: &Intersections<'a>) -> (Num, Num) {
+        let mut containers: Vec<&dyn Shape> = vec![];
+        let index = |containers: &[&dyn Shape]| {
+            containers
+                .last()
+                .map_or(1.0, |o| o.material().refractive_index)
+        };
+        for i in xs.iter() {
+            let n1 = index(&containers);
+            match containers.iter().position(|&o| o == i.object) {
+                Some(pos) => {
+                    containers.remove(pos);
+                }
+                None => containers.push(i.object),
+            }
+            if i == self {
+                return (n1, index(&containers));
+            }
+        }
+        (1.0, 1.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Computations<'a> {
+    pub t: Num,
+    pub object: &'a dyn Shape,
+    pub point: Point,
+    pub over_point: Point,
+    pub under_point: Point,
+    pub eyev: Vector,
+    pub normalv: Vector,
+    pub reflectv: Vector,
+    pub inside: bool,
+    /// The refractive index of the material being exited.
+    pub n1: Num,
+    /// The refractive index of the material being entered.
+    pub n2: Num,
 }
 
-impl From<Sphere> for Intersected {
-    fn from(value: Sphere) -> Self {
-        Intersected::Sphere(value)
+impl Computations<'_> {
+    /// The fraction of light that is reflected rather than refracted at
+    /// the hit, using Schlick's approximation of the Fresnel equations.
+    pub fn schlick(&self) -> Num {
+        let mut cos = self.eyev.dot(self.normalv);
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+            if sin2_t > 1.0 {
+                // total internal reflection
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
 
     #[test]
     fn an_intersection_encapsulates_t_and_object() {
         let s = sphere();
-        let i = intersection(3.5, s);
+        let i = intersection(3.5, &s);
         assert_eq!(i.t, 3.5);
-        assert_eq!(i.object, s);
+        assert_eq!(i.object, &s as &dyn Shape);
+    }
+
+    #[test]
+    fn an_intersection_can_encapsulate_u_and_v() {
+        let s = triangle(point(0, 1, 0), point(-1, 0, 0), point(1, 0, 0));
+        let i = intersection(3.5, &s).with_uv(0.2, 0.4);
+        assert_eq!(i.u, 0.2);
+        assert_eq!(i.v, 0.4);
     }
 
     #[test]
     fn aggregating_intersections() {
         let s = sphere();
-        let i1 = intersection(1, s);
-        let i2 = intersection(2, s);
+        let i1 = intersection(1, &s);
+        let i2 = intersection(2, &s);
         let xs = intersections([i1, i2]);
         assert_eq!(xs.iter().map(|i| i.t).collect_vec(), vec![1.0, 2.0]);
     }
@@ -92,8 +222,8 @@ mod tests {
     #[test]
     fn the_hit_all_xs_have_positive_t() {
         let s = sphere();
-        let i1 = intersection(1, s);
-        let i2 = intersection(2, s);
+        let i1 = intersection(1, &s);
+        let i2 = intersection(2, &s);
         let xs = intersections([i2, i1]);
         assert_eq!(xs.hit(), Some(i1));
     }
@@ -101,8 +231,8 @@ mod tests {
     #[test]
     fn the_hit_all_some_xs_have_negative_t() {
         let s = sphere();
-        let i1 = intersection(-1, s);
-        let i2 = intersection(2, s);
+        let i1 = intersection(-1, &s);
+        let i2 = intersection(2, &s);
         let xs = intersections([i2, i2]);
         assert_eq!(xs.hit(), Some(i2));
     }
@@ -110,8 +240,8 @@ mod tests {
     #[test]
     fn the_hit_all_xs_negative_t() {
         let s = sphere();
-        let i1 = intersection(-2, s);
-        let i2 = intersection(-1, s);
+        let i1 = intersection(-2, &s);
+        let i2 = intersection(-1, &s);
         let xs = intersections([i2, i2]);
         assert_eq!(xs.hit(), None);
     }
@@ -119,11 +249,153 @@ mod tests {
     #[test]
     fn the_hit_is_always_the_lowest_nonneg_intersection() {
         let s = sphere();
-        let i1 = intersection(5, s);
-        let i2 = intersection(7, s);
-        let i3 = intersection(-3, s);
-        let i4 = intersection(2, s);
+        let i1 = intersection(5, &s);
+        let i2 = intersection(7, &s);
+        let i3 = intersection(-3, &s);
+        let i4 = intersection(2, &s);
         let xs = intersections([i1, i2, i3, i4]);
         assert_eq!(xs.hit(), Some(i4));
     }
+
+    #[test]
+    fn sorting_intersections_orders_by_t() {
+        let s = sphere();
+        let xs = intersections([
+            intersection(5, &s),
+            intersection(-3, &s),
+            intersection(2, &s),
+        ]);
+        assert_eq!(
+            xs.sorted().iter().map(|i| i.t).collect_vec(),
+            vec![-3.0, 2.0, 5.0]
+        );
+    }
+
+    #[test]
+    fn precomputing_the_state_of_an_intersection() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let shape = sphere();
+        let i = intersection(4, &shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(comps.t, i.t);
+        assert_eq!(comps.object, &shape as &dyn Shape);
+        assert_eq!(comps.point, point(0, 0, -1));
+        assert_eq!(comps.eyev, vector(0, 0, -1));
+        assert_eq!(comps.normalv, vector(0, 0, -1));
+    }
+
+    #[test]
+    fn the_hit_when_an_intersection_occurs_on_the_outside() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let s = sphere();
+        let i = intersection(4, &s);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn the_hit_when_an_intersection_occurs_on_the_inside() {
+        let r = ray(point(0, 0, 0), vector(0, 0, 1));
+        let s = sphere();
+        let i = intersection(1, &s);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(comps.point, point(0, 0, 1));
+        assert_eq!(comps.eyev, vector(0, 0, -1));
+        assert!(comps.inside);
+        // normal would have been (0, 0, 1), but is inverted
+        assert_eq!(comps.normalv, vector(0, 0, -1));
+    }
+
+    #[test]
+    fn the_hit_should_offset_the_point() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let shape = sphere().with_transform(translation(0, 0, 1));
+        let i = intersection(5, &shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert!(comps.over_point.z() < -EPSILON / 2.0);
+        assert!(comps.point.z() > comps.over_point.z());
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let shape = plane();
+        let r = ray(point(0, 1, -1), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = intersection(SQRT_2, &shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(comps.reflectv, vector(0, FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let a = glass_sphere()
+            .with_transform(scaling(2, 2, 2))
+            .with_material(glass_sphere().material().clone().with_refractive_index(1.5));
+        let b = glass_sphere()
+            .with_transform(translation(0, 0, -0.25))
+            .with_material(glass_sphere().material().clone().with_refractive_index(2.0));
+        let c = glass_sphere()
+            .with_transform(translation(0, 0, 0.25))
+            .with_material(glass_sphere().material().clone().with_refractive_index(2.5));
+        let r = ray(point(0, 0, -4), vector(0, 0, 1));
+        let xs = intersections([
+            intersection(2, &a),
+            intersection(2.75, &b),
+            intersection(3.25, &c),
+            intersection(4.75, &b),
+            intersection(5.25, &c),
+            intersection(6, &a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in xs.iter().zip(expected) {
+            let comps = i.prepare_computations(r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2));
+        }
+    }
+
+    #[test]
+    fn the_under_point_is_offset_below_the_surface() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let shape = glass_sphere().with_transform(translation(0, 0, 1));
+        let i = intersection(5, &shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert!(comps.under_point.z() > EPSILON / 2.0);
+        assert!(comps.point.z() < comps.under_point.z());
+    }
+
+    #[test]
+    fn the_schlick_approximation_under_total_internal_reflection() {
+        let shape = glass_sphere();
+        let r = ray(point(0, 0, FRAC_1_SQRT_2), vector(0, 1, 0));
+        let xs = intersections([
+            intersection(-FRAC_1_SQRT_2, &shape),
+            intersection(FRAC_1_SQRT_2, &shape),
+        ]);
+        let comps = xs[1].prepare_computations(r, &xs);
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere();
+        let r = ray(point(0, 0, 0), vector(0, 1, 0));
+        let xs = intersections([intersection(-1, &shape), intersection(1, &shape)]);
+        let comps = xs[1].prepare_computations(r, &xs);
+        assert!(nums_equal(comps.schlick(), 0.04));
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere();
+        let r = ray(point(0, 0.99, -2), vector(0, 0, 1));
+        let xs = intersections([intersection(1.8589, &shape)]);
+        let comps = xs[0].prepare_computations(r, &xs);
+        assert!(nums_equal(comps.schlick(), 0.48873));
+    }
 }
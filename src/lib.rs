@@ -1,11 +1,49 @@
 #![allow(dead_code, unused)]
 
+pub mod blend;
+pub mod bounds;
+pub mod bump;
+pub mod camera;
 pub mod canvas;
+pub mod checker;
 pub mod color;
+pub mod cone;
 pub mod coord;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod draw;
+pub mod exr;
+pub mod gradient;
+pub mod group;
 pub mod intersection;
+pub mod light;
+pub mod marble;
+pub mod material;
 pub mod matrix;
+pub mod noise;
+pub mod obj;
+pub mod pattern;
+pub mod perturbed;
+pub mod pfm;
+pub mod plane;
+pub mod png;
+pub mod ppm;
 pub mod prelude;
 pub mod rays;
+pub mod region;
+pub mod render;
+pub mod ring;
+pub mod sampler;
+pub mod shape;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod stats;
+pub mod stripe;
+pub mod texture;
+pub mod tonemap;
 pub mod transform;
+pub mod triangle;
+pub mod uv;
+pub mod wood;
+pub mod world;
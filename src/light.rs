@@ -0,0 +1,154 @@
+use super::prelude::*;
+
+pub fn point_light(position: Point, intensity: Color) -> PointLight {
+    PointLight::new(position, intensity)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: Point,
+    pub intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+/// Shades a point on a surface using the Phong reflection model.
+pub fn lighting(
+    material: &Material,
+    object: &dyn Shape,
+    light: &PointLight,
+    point: Point,
+    eyev: Vector,
+    normalv: Vector,
+    in_shadow: bool,
+) -> Color {
+    let black = color(0, 0, 0);
+    let effective_color = material.color_at(object, point) * light.intensity;
+    let lightv = (light.position - point).normalize();
+    let ambient = effective_color.mul_scalar(material.ambient);
+    if in_shadow {
+        return ambient;
+    }
+    let light_dot_normal = lightv.dot(normalv);
+    if light_dot_normal < 0.0 {
+        // the light is on the other side of the surface
+        return ambient;
+    }
+    let diffuse = effective_color.mul_scalar(material.diffuse * light_dot_normal);
+    let reflectv = (-lightv).reflect(normalv);
+    let reflect_dot_eye = reflectv.dot(eyev);
+    let specular = if reflect_dot_eye <= 0.0 {
+        // the light reflects away from the eye
+        black
+    } else {
+        let factor = reflect_dot_eye.powf(material.shininess);
+        light.intensity.mul_scalar(material.specular * factor)
+    };
+    ambient + diffuse + specular
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn a_point_light_has_a_position_and_intensity() {
+        let intensity = color(1, 1, 1);
+        let position = point(0, 0, 0);
+        let light = point_light(position, intensity);
+        assert_eq!(light.position, position);
+        assert_eq!(light.intensity, intensity);
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_the_light_and_the_surface() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, 0, -1);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 0, -10), color(1, 1, 1));
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, false);
+        assert_eq!(result, color(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_light_and_surface_eye_offset_45_degrees() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 0, -10), color(1, 1, 1));
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, false);
+        assert_eq!(result, color(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn lighting_with_eye_opposite_surface_light_offset_45_degrees() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, 0, -1);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 10, -10), color(1, 1, 1));
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, false);
+        assert_eq!(result, color(0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn lighting_with_eye_in_the_path_of_the_reflection_vector() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, -FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 10, -10), color(1, 1, 1));
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, false);
+        assert_eq!(result, color(1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn lighting_with_the_light_behind_the_surface() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, 0, -1);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 0, 10), color(1, 1, 1));
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, false);
+        assert_eq!(result, color(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_the_surface_in_shadow() {
+        let m = material();
+        let position = point(0, 0, 0);
+        let eyev = vector(0, 0, -1);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 0, -10), color(1, 1, 1));
+        let in_shadow = true;
+        let result = lighting(&m, &sphere(), &light, position, eyev, normalv, in_shadow);
+        assert_eq!(result, color(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_a_pattern_applied() {
+        let m = material()
+            .with_pattern(stripe_pattern(color(1, 1, 1), color(0, 0, 0)))
+            .with_ambient(1)
+            .with_diffuse(0)
+            .with_specular(0);
+        let object = sphere();
+        let eyev = vector(0, 0, -1);
+        let normalv = vector(0, 0, -1);
+        let light = point_light(point(0, 0, -10), color(1, 1, 1));
+        let c1 = lighting(&m, &object, &light, point(0.9, 0, 0), eyev, normalv, false);
+        let c2 = lighting(&m, &object, &light, point(1.1, 0, 0), eyev, normalv, false);
+        assert_eq!(c1, color(1, 1, 1));
+        assert_eq!(c2, color(0, 0, 0));
+    }
+}
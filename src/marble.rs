@@ -0,0 +1,91 @@
+use super::prelude::*;
+
+pub fn marble_pattern(a: Color, b: Color, noise: Noise) -> MarblePattern {
+    MarblePattern::new(a, b, noise)
+}
+
+/// Veins of `b` running through `a`, made by bending a sine wave along x
+/// with turbulence.
+#[derive(Clone, Debug)]
+pub struct MarblePattern {
+    props: PatternProps,
+    a: Color,
+    b: Color,
+    noise: Noise,
+    frequency: Num,
+    turbulence: Num,
+    octaves: usize,
+}
+
+impl MarblePattern {
+    pub fn new(a: Color, b: Color, noise: Noise) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a,
+            b,
+            noise,
+            frequency: 1.0,
+            turbulence: 5.0,
+            octaves: 6,
+        }
+    }
+
+    /// How many veins there are per unit along x.
+    pub fn with_frequency(mut self, frequency: impl Into<Num>) -> Self {
+        self.frequency = frequency.into();
+        self
+    }
+
+    /// How strongly the veins are bent.
+    pub fn with_turbulence(mut self, turbulence: impl Into<Num>) -> Self {
+        self.turbulence = turbulence.into();
+        self
+    }
+
+    pub fn with_octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl Pattern for MarblePattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let turbulence = self.noise.turbulence(point, self.octaves);
+        let phase =
+            (point.x() * self.frequency + turbulence * self.turbulence) * std::f64::consts::PI;
+        let t = 0.5 + 0.5 * phase.sin();
+        self.a + (self.b - self.a).mul_scalar(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marble_without_turbulence_is_a_smooth_wave() {
+        let pattern = marble_pattern(color(0, 0, 0), color(1, 1, 1), noise(1)).with_turbulence(0);
+        assert_eq!(pattern.color_at(point(0, 0.3, 0.7)), color(0.5, 0.5, 0.5));
+        assert_eq!(pattern.color_at(point(0.5, 0.3, 0.7)), color(1, 1, 1));
+        assert_eq!(pattern.color_at(point(1.5, 0.3, 0.7)), color(0, 0, 0));
+    }
+
+    #[test]
+    fn marble_stays_between_its_colors() {
+        let a = color(0.2, 0.2, 0.2);
+        let b = color(0.9, 0.9, 0.9);
+        let pattern = marble_pattern(a, b, noise(4));
+        for i in 0..100 {
+            let c = pattern.color_at(point(i as Num * 0.21, i as Num * 0.05, 0.3));
+            assert!(c.r() >= 0.2 - EPSILON && c.r() <= 0.9 + EPSILON);
+        }
+    }
+}
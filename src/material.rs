@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn material() -> Material {
+    Material::default()
+}
+
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub color: Color,
+    /// Paints the surface instead of the flat `color` when set.
+    pub pattern: Option<Arc<dyn Pattern>>,
+    pub ambient: Num,
+    pub diffuse: Num,
+    pub specular: Num,
+    pub shininess: Num,
+    /// How much of the scene is mirrored by the surface, from 0 (not at
+    /// all) to 1 (a perfect mirror).
+    pub reflective: Num,
+    /// How much light passes through the surface, from 0 (opaque) to 1.
+    pub transparency: Num,
+    /// How much light bends when entering the material, e.g. 1.0 for a
+    /// vacuum, 1.33 for water and 1.5 for glass.
+    pub refractive_index: Num,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: color(1, 1, 1),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+        }
+    }
+}
+
+/// Patterns can't be compared by value, so materials only have equal
+/// patterns when they share the very same one.
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        let same_pattern = match (&self.pattern, &other.pattern) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_pattern
+            && self.color == other.color
+            && self.ambient == other.ambient
+            && self.diffuse == other.diffuse
+            && self.specular == other.specular
+            && self.shininess == other.shininess
+            && self.reflective == other.reflective
+            && self.transparency == other.transparency
+            && self.refractive_index == other.refractive_index
+    }
+}
+
+impl Material {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: impl IntoPattern) -> Self {
+        self.pattern = Some(pattern.into_pattern());
+        self
+    }
+
+    /// The color of the surface of `object` at the given world point.
+    pub fn color_at(&self, object: &dyn Shape, world_point: Point) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.color_at_shape(object, world_point),
+            None => self.color,
+        }
+    }
+
+    pub fn with_ambient(mut self, ambient: impl Into<Num>) -> Self {
+        self.ambient = ambient.into();
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: impl Into<Num>) -> Self {
+        self.diffuse = diffuse.into();
+        self
+    }
+
+    pub fn with_specular(mut self, specular: impl Into<Num>) -> Self {
+        self.specular = specular.into();
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: impl Into<Num>) -> Self {
+        self.shininess = shininess.into();
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: impl Into<Num>) -> Self {
+        self.reflective = reflective.into();
+        self
+    }
+
+    pub fn with_transparency(mut self, transparency: impl Into<Num>) -> Self {
+        self.transparency = transparency.into();
+        self
+    }
+
+    pub fn with_refractive_index(mut self, refractive_index: impl Into<Num>) -> Self {
+        self.refractive_index = refractive_index.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_material() {
+        let m = material();
+        assert_eq!(m.color, color(1, 1, 1));
+        assert!(m.pattern.is_none());
+        assert_eq!(m.ambient, 0.1);
+        assert_eq!(m.diffuse, 0.9);
+        assert_eq!(m.specular, 0.9);
+        assert_eq!(m.shininess, 200.0);
+    }
+
+    #[test]
+    fn reflectivity_for_the_default_material() {
+        let m = material();
+        assert_eq!(m.reflective, 0.0);
+    }
+
+    #[test]
+    fn transparency_and_refractive_index_for_the_default_material() {
+        let m = material();
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+    }
+
+    #[test]
+    fn materials_with_a_pattern_only_equal_those_sharing_it() {
+        let m1 = material().with_pattern(stripe_pattern(color(1, 1, 1), color(0, 0, 0)));
+        let m2 = material().with_pattern(stripe_pattern(color(1, 1, 1), color(0, 0, 0)));
+        assert_eq!(m1, m1.clone());
+        assert_ne!(m1, m2);
+        assert_ne!(m1, material());
+    }
+}
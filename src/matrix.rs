@@ -1,6 +1,5 @@
 use super::prelude::*;
 use once_cell::sync::Lazy;
-use std::usize;
 
 pub static IDENTITY: Lazy<Matrix> = Lazy::new(|| {
     let mut m = Matrix::Matrix4([0.0; 16]);
@@ -0,0 +1,227 @@
+use std::fmt;
+
+use super::prelude::*;
+
+pub fn noise(seed: u64) -> Noise {
+    Noise::new(seed)
+}
+
+/// Three dimensional gradient (Perlin) noise. The gradients are chosen by a
+/// permutation table shuffled from the seed, so the same seed always
+/// produces the same noise.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Noise {
+    seed: u64,
+    perm: Box<[u8; 512]>,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Debug for Noise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Noise").field("seed", &self.seed).finish()
+    }
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        // Fisher-Yates shuffle driven by splitmix64, which is plenty random
+        // for picking gradients and keeps the crate free of dependencies
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        let perm = Box::new(std::array::from_fn(|i| table[i % 256]));
+        Self { seed, perm }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The noise at a point, roughly between -1 and 1. It is zero at every
+    /// integer lattice point and varies smoothly in between.
+    pub fn sample(&self, point: Point) -> Num {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let (xf, yf, zf) = (x.floor(), y.floor(), z.floor());
+        let (xi, yi, zi) = (wrap(xf), wrap(yf), wrap(zf));
+        let (x, y, z) = (x - xf, y - yf, z - zf);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let p = &self.perm;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each at twice
+    /// the frequency and half the amplitude of the last. Like `sample`, the
+    /// result is roughly between -1 and 1.
+    pub fn fbm(&self, p: Point, octaves: usize) -> Num {
+        self.octaves(p, octaves, |n| n)
+    }
+
+    /// Like `fbm`, but summing the absolute value of each layer, which
+    /// gives sharp creases where the noise crosses zero. Between 0 and 1.
+    pub fn turbulence(&self, p: Point, octaves: usize) -> Num {
+        self.octaves(p, octaves, Num::abs)
+    }
+
+    fn octaves(&self, p: Point, octaves: usize, f: impl Fn(Num) -> Num) -> Num {
+        let (mut sum, mut total, mut frequency, mut amplitude) = (0.0, 0.0, 1.0, 1.0);
+        for _ in 0..octaves.max(1) {
+            let scaled = point(p.x() * frequency, p.y() * frequency, p.z() * frequency);
+            sum += f(self.sample(scaled)) * amplitude;
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum / total
+    }
+}
+
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn wrap(n: Num) -> usize {
+    (n as i64).rem_euclid(256) as usize
+}
+
+/// Perlin's 6t^5 - 15t^4 + 10t^3 easing curve.
+fn fade(t: Num) -> Num {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: Num, a: Num, b: Num) -> Num {
+    a + t * (b - a)
+}
+
+/// The dot product of the offset with one of twelve gradient directions
+/// picked by the hash.
+fn grad(hash: u8, x: Num, y: Num, z: Num) -> Num {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_points() -> impl Iterator<Item = Point> {
+        (0..200).map(|i| {
+            let i = i as Num;
+            point(i * 0.37 - 20.0, i * 0.11 + 3.3, i * -0.23 + 1.7)
+        })
+    }
+
+    #[test]
+    fn noise_is_deterministic_for_a_seed() {
+        let n1 = noise(42);
+        let n2 = noise(42);
+        for p in sample_points() {
+            assert_eq!(n1.sample(p), n2.sample(p));
+        }
+    }
+
+    #[test]
+    fn different_seeds_produce_different_noise() {
+        let n1 = noise(1);
+        let n2 = noise(2);
+        assert!(sample_points().any(|p| n1.sample(p) != n2.sample(p)));
+    }
+
+    #[test]
+    fn noise_is_zero_at_lattice_points() {
+        let n = noise(7);
+        for p in [point(0, 0, 0), point(1, 2, 3), point(-4, 5, -6)] {
+            assert_eq!(n.sample(p), 0.0);
+        }
+    }
+
+    #[test]
+    fn noise_stays_within_its_range() {
+        let n = noise(3);
+        for p in sample_points() {
+            let v = n.sample(p);
+            assert!((-1.0..=1.0).contains(&v), "{v} out of range at {p:?}");
+            let v = n.turbulence(p, 4);
+            assert!((0.0..=1.0).contains(&v), "{v} out of range at {p:?}");
+            let v = n.fbm(p, 4);
+            assert!((-1.0..=1.0).contains(&v), "{v} out of range at {p:?}");
+        }
+    }
+
+    #[test]
+    fn noise_varies_smoothly() {
+        let n = noise(5);
+        for p in sample_points() {
+            let nearby = p.add_vector(vector(0.001, 0.001, 0.001));
+            assert!((n.sample(p) - n.sample(nearby)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn noise_is_not_constant() {
+        let n = noise(9);
+        let values = sample_points().map(|p| n.sample(p)).collect_vec();
+        assert!(values.iter().any(|&v| v > 0.1));
+        assert!(values.iter().any(|&v| v < -0.1));
+    }
+
+    #[test]
+    fn a_single_octave_of_fbm_is_plain_noise() {
+        let n = noise(11);
+        for p in sample_points() {
+            assert_eq!(n.fbm(p, 1), n.sample(p));
+            assert_eq!(n.turbulence(p, 1), n.sample(p).abs());
+        }
+    }
+}
@@ -0,0 +1,515 @@
+//! A parser for the subset of the Wavefront OBJ format needed to load
+//! triangle meshes: vertices (`v`), vertex normals (`vn`), faces (`f`) and
+//! named groups (`g`). Anything else is skipped and reported back.
+
+use std::{error, fmt, fs, io, path::Path};
+
+use super::prelude::*;
+
+pub fn parse_obj(input: &str) -> Result<ObjFile, ObjError> {
+    ObjFile::parse(input)
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl ObjError {
+    fn parse(line: usize, message: impl Into<String>) -> Self {
+        ObjError::Parse {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "could not read obj file: {err}"),
+            ObjError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ObjError::Io(err) => Some(err),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(value: io::Error) -> Self {
+        ObjError::Io(value)
+    }
+}
+
+/// A line that the parser did not understand and skipped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjLine {
+    pub number: usize,
+    pub text: String,
+}
+
+/// The faces declared under a `g` statement. Faces declared before any
+/// group end up in the default group, which has an empty name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub triangles: Vec<Triangle>,
+    pub smooth_triangles: Vec<SmoothTriangle>,
+}
+
+impl ObjGroup {
+    fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty() && self.smooth_triangles.is_empty()
+    }
+
+    pub fn into_group(self) -> Group {
+        let mut g = group();
+        for t in self.triangles {
+            g.add_child(t);
+        }
+        for t in self.smooth_triangles {
+            g.add_child(t);
+        }
+        g
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjFile {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    groups: Vec<ObjGroup>,
+    ignored: Vec<ObjLine>,
+}
+
+impl Default for ObjFile {
+    fn default() -> Self {
+        Self {
+            vertices: vec![],
+            normals: vec![],
+            groups: vec![ObjGroup::default()],
+            ignored: vec![],
+        }
+    }
+}
+
+impl ObjFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ObjError> {
+        let input = fs::read_to_string(path)?;
+        Self::parse(&input)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ObjError> {
+        let mut obj = ObjFile::default();
+        let mut current = 0;
+        for (idx, text) in input.lines().enumerate() {
+            let number = idx + 1;
+            let mut fields = text.split_whitespace();
+            match fields.next() {
+                None => {}
+                Some(comment) if comment.starts_with('#') => {}
+                Some("v") => {
+                    let [x, y, z] = parse_nums(number, fields)?;
+                    obj.vertices.push(point(x, y, z));
+                }
+                Some("vn") => {
+                    let [x, y, z] = parse_nums(number, fields)?;
+                    obj.normals.push(vector(x, y, z));
+                }
+                Some("f") => {
+                    let group = &mut obj.groups[current];
+                    add_face(number, fields, &obj.vertices, &obj.normals, group)?;
+                }
+                Some("g") => {
+                    let name = fields.collect::<Vec<_>>().join(" ");
+                    current = match obj.groups.iter().position(|g| g.name == name) {
+                        Some(existing) => existing,
+                        None => {
+                            obj.groups.push(ObjGroup::new(name));
+                            obj.groups.len() - 1
+                        }
+                    };
+                }
+                Some(_) => obj.ignored.push(ObjLine {
+                    number,
+                    text: text.to_string(),
+                }),
+            }
+        }
+        Ok(obj)
+    }
+
+    /// Looks up a vertex using the 1-based numbering of the file.
+    pub fn vertex(&self, idx: usize) -> Option<Point> {
+        idx.checked_sub(1)
+            .and_then(|i| self.vertices.get(i))
+            .copied()
+    }
+
+    /// Looks up a vertex normal using the 1-based numbering of the file.
+    pub fn normal(&self, idx: usize) -> Option<Vector> {
+        idx.checked_sub(1)
+            .and_then(|i| self.normals.get(i))
+            .copied()
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    pub fn default_group(&self) -> &ObjGroup {
+        &self.groups[0]
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// All of the groups in the order they were declared, starting with the
+    /// default group.
+    pub fn groups(&self) -> &[ObjGroup] {
+        &self.groups
+    }
+
+    pub fn ignored(&self) -> &[ObjLine] {
+        &self.ignored
+    }
+
+    /// Converts the file into a single group holding one child group for
+    /// each of the non-empty groups in the file. Models tend to have a lot
+    /// of triangles, so the groups are divided into a bounding volume
+    /// hierarchy using the default threshold.
+    pub fn into_group(self) -> Group {
+        let mut g = group();
+        for obj_group in self.groups.into_iter().filter(|g| !g.is_empty()) {
+            g.add_child(obj_group.into_group());
+        }
+        g.divide(DEFAULT_DIVIDE_THRESHOLD);
+        g
+    }
+}
+
+fn parse_nums<'a>(
+    line: usize,
+    fields: impl Iterator<Item = &'a str>,
+) -> Result<[Num; 3], ObjError> {
+    let nums = fields
+        .map(|f| {
+            f.parse::<Num>()
+                .map_err(|_| ObjError::parse(line, format!("invalid number '{f}'")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match nums[..] {
+        // a fourth (w) component is allowed but not used
+        [x, y, z] | [x, y, z, _] => Ok([x, y, z]),
+        _ => Err(ObjError::parse(
+            line,
+            format!("expected 3 numbers but found {}", nums.len()),
+        )),
+    }
+}
+
+/// Resolves a possibly negative (relative) 1-based index into a 0-based one.
+fn resolve_index(line: usize, field: &str, len: usize, what: &str) -> Result<usize, ObjError> {
+    let idx = field
+        .parse::<isize>()
+        .map_err(|_| ObjError::parse(line, format!("invalid {what} index '{field}'")))?;
+    let resolved = if idx < 0 { len as isize + idx } else { idx - 1 };
+    if resolved < 0 || resolved as usize >= len {
+        return Err(ObjError::parse(
+            line,
+            format!("{what} index {idx} out of range (have {len})"),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parses a face and fans it out into triangles, which are smooth when
+/// every vertex of the face has a normal.
+fn add_face<'a>(
+    line: usize,
+    fields: impl Iterator<Item = &'a str>,
+    vertices: &[Point],
+    normals: &[Vector],
+    group: &mut ObjGroup,
+) -> Result<(), ObjError> {
+    let mut points = vec![];
+    let mut face_normals = vec![];
+    for field in fields {
+        // each field is v, v/vt, v//vn, or v/vt/vn
+        let mut parts = field.split('/');
+        let v = parts.next().unwrap_or_default();
+        let v = resolve_index(line, v, vertices.len(), "vertex")?;
+        points.push(vertices[v]);
+        if let Some(vn) = parts.nth(1).filter(|vn| !vn.is_empty()) {
+            let vn = resolve_index(line, vn, normals.len(), "normal")?;
+            face_normals.push(normals[vn]);
+        }
+    }
+    if points.len() < 3 {
+        return Err(ObjError::parse(
+            line,
+            format!(
+                "a face needs at least 3 vertices but found {}",
+                points.len()
+            ),
+        ));
+    }
+    let smooth = face_normals.len() == points.len();
+    for idx in 1..points.len() - 1 {
+        let (p1, p2, p3) = (points[0], points[idx], points[idx + 1]);
+        if smooth {
+            let (n1, n2, n3) = (face_normals[0], face_normals[idx], face_normals[idx + 1]);
+            group
+                .smooth_triangles
+                .push(smooth_triangle(p1, p2, p3, n1, n2, n3));
+        } else {
+            group.triangles.push(triangle(p1, p2, p3));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let obj = parse_obj(gibberish).unwrap();
+        assert_eq!(obj.ignored().len(), 5);
+        assert_eq!(obj.ignored()[1].number, 2);
+        assert_eq!(
+            obj.ignored()[1].text,
+            "who traveled much faster than light."
+        );
+    }
+
+    #[test]
+    fn vertex_records() {
+        let file = "
+v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+        let obj = parse_obj(file).unwrap();
+        assert_eq!(obj.vertex(1), Some(point(-1, 1, 0)));
+        assert_eq!(obj.vertex(2), Some(point(-1, 0.5, 0)));
+        assert_eq!(obj.vertex(3), Some(point(1, 0, 0)));
+        assert_eq!(obj.vertex(4), Some(point(1, 1, 0)));
+        assert_eq!(obj.vertex(0), None);
+        assert!(obj.ignored().is_empty());
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let file = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let obj = parse_obj(file).unwrap();
+        let g = obj.default_group();
+        let (t1, t2) = (&g.triangles[0], &g.triangles[1]);
+        assert_eq!(t1.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t1.p2(), obj.vertex(2).unwrap());
+        assert_eq!(t1.p3(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t2.p2(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p3(), obj.vertex(4).unwrap());
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let obj = parse_obj(file).unwrap();
+        let g = obj.default_group();
+        assert_eq!(g.triangles.len(), 3);
+        let (t1, t2, t3) = (&g.triangles[0], &g.triangles[1], &g.triangles[2]);
+        assert_eq!(t1.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t1.p2(), obj.vertex(2).unwrap());
+        assert_eq!(t1.p3(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t2.p2(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p3(), obj.vertex(4).unwrap());
+        assert_eq!(t3.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t3.p2(), obj.vertex(4).unwrap());
+        assert_eq!(t3.p3(), obj.vertex(5).unwrap());
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let file = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let obj = parse_obj(file).unwrap();
+        assert!(obj.default_group().is_empty());
+        let t1 = &obj.group("FirstGroup").unwrap().triangles[0];
+        let t2 = &obj.group("SecondGroup").unwrap().triangles[0];
+        assert_eq!(t1.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t1.p2(), obj.vertex(2).unwrap());
+        assert_eq!(t1.p3(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t2.p2(), obj.vertex(3).unwrap());
+        assert_eq!(t2.p3(), obj.vertex(4).unwrap());
+    }
+
+    #[test]
+    fn converting_an_obj_file_to_a_group() {
+        let file = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let g = parse_obj(file).unwrap().into_group();
+        assert_eq!(g.children().len(), 2);
+        for child in g.children() {
+            assert_eq!(child.children().len(), 1);
+        }
+        let r = ray(point(-0.5, 0.5, -2), vector(0, 0, 1));
+        assert_eq!(g.intersect(r).len(), 1);
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let file = "
+vn 0 0 1
+vn 0.6 0 -0.8
+vn 1 2 3";
+        let obj = parse_obj(file).unwrap();
+        assert_eq!(obj.normal(1), Some(vector(0, 0, 1)));
+        assert_eq!(obj.normal(2), Some(vector(0.6, 0, -0.8)));
+        assert_eq!(obj.normal(3), Some(vector(1, 2, 3)));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let file = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/0/3 2/102/1 3/14/2";
+        let obj = parse_obj(file).unwrap();
+        let g = obj.default_group();
+        assert!(g.triangles.is_empty());
+        let (t1, t2) = (&g.smooth_triangles[0], &g.smooth_triangles[1]);
+        assert_eq!(t1.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t1.p2(), obj.vertex(2).unwrap());
+        assert_eq!(t1.p3(), obj.vertex(3).unwrap());
+        assert_eq!(t1.n1(), obj.normal(3).unwrap());
+        assert_eq!(t1.n2(), obj.normal(1).unwrap());
+        assert_eq!(t1.n3(), obj.normal(2).unwrap());
+        assert_eq!(t1, t2);
+    }
+
+    #[test]
+    fn negative_indices_are_relative_to_the_end() {
+        let file = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1";
+        let obj = parse_obj(file).unwrap();
+        let t = &obj.default_group().triangles[0];
+        assert_eq!(t.p1(), obj.vertex(1).unwrap());
+        assert_eq!(t.p3(), obj.vertex(3).unwrap());
+    }
+
+    #[test]
+    fn malformed_vertices_report_the_line() {
+        let file = "v 0 1 0\nv -1 zero 0";
+        let err = parse_obj(file).unwrap_err();
+        assert!(
+            matches!(&err, ObjError::Parse { line: 2, .. }),
+            "unexpected error: {err}"
+        );
+        assert_eq!(err.to_string(), "line 2: invalid number 'zero'");
+    }
+
+    #[test]
+    fn faces_referring_to_missing_vertices_report_the_line() {
+        let file = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\nf 1 2 4";
+        let err = parse_obj(file).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 5: vertex index 4 out of range (have 3)"
+        );
+    }
+
+    #[test]
+    fn converting_a_large_obj_file_builds_a_hierarchy() {
+        let mut file = String::new();
+        for i in 0..20 {
+            let x = i * 3;
+            file += &format!("v {x} 0 0\nv {} 0 0\nv {x} 1 0\n", x + 1);
+            file += &format!("f {} {} {}\n", i * 3 + 1, i * 3 + 2, i * 3 + 3);
+        }
+        let g = parse_obj(&file).unwrap().into_group();
+        assert_eq!(g.primitive_count(), 20);
+        let model = &g.children()[0];
+        assert!(model.children().len() <= DEFAULT_DIVIDE_THRESHOLD);
+        let r = ray(point(30.25, 0.25, -2), vector(0, 0, 1));
+        take_stats();
+        assert_eq!(g.intersect(r).len(), 1);
+        assert!(take_stats().primitive_tests < 20);
+    }
+
+    #[test]
+    fn faces_need_three_vertices() {
+        let file = "v 0 1 0\nv -1 0 0\nf 1 2";
+        let err = parse_obj(file).unwrap_err();
+        assert!(matches!(err, ObjError::Parse { line: 3, .. }));
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn solid_pattern(color: Color) -> SolidPattern {
+    SolidPattern::new(color)
+}
+
+/// The state every pattern carries. Like shapes, patterns have their own
+/// transform, which is applied after the object's so that a pattern can be
+/// scaled or rotated independently of the shape it is painted on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternProps {
+    transform: Matrix,
+    inverse: Matrix,
+}
+
+impl Default for PatternProps {
+    fn default() -> Self {
+        Self {
+            transform: identity(),
+            inverse: identity(),
+        }
+    }
+}
+
+impl PatternProps {
+    pub fn transform(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn set_transform(&mut self, tf: Matrix) {
+        self.transform = tf;
+        self.inverse = tf.inverse();
+    }
+}
+
+/// Something that paints a color onto every point of a surface.
+/// Implementors only deal with pattern space; `color_at` takes care of
+/// converting points into it.
+pub trait Pattern: fmt::Debug + Send + Sync {
+    fn props(&self) -> &PatternProps;
+
+    fn props_mut(&mut self) -> &mut PatternProps;
+
+    fn local_color_at(&self, point: Point) -> Color;
+
+    fn transform(&self) -> Matrix {
+        self.props().transform()
+    }
+
+    fn set_transform(&mut self, tf: Matrix) {
+        self.props_mut().set_transform(tf);
+    }
+
+    fn with_transform(mut self, tf: Matrix) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_transform(tf);
+        self
+    }
+
+    /// The color at a point given in the space that contains the pattern:
+    /// object space for a pattern painted on a shape, or the parent's
+    /// pattern space for a nested pattern.
+    fn color_at(&self, point: Point) -> Color {
+        self.local_color_at(self.props().inverse().mul_point(point))
+    }
+
+    /// The color at a point in world space on the given shape.
+    fn color_at_shape(&self, shape: &dyn Shape, world_point: Point) -> Color {
+        self.color_at(shape.world_to_object(world_point))
+    }
+}
+
+/// Anything that can be used where a pattern is expected. Plain colors
+/// become solid patterns, which is what allows patterns to be nested.
+pub trait IntoPattern {
+    fn into_pattern(self) -> Arc<dyn Pattern>;
+}
+
+impl<P: Pattern + 'static> IntoPattern for P {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        Arc::new(self)
+    }
+}
+
+impl IntoPattern for Arc<dyn Pattern> {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        self
+    }
+}
+
+impl IntoPattern for Color {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        Arc::new(solid_pattern(self))
+    }
+}
+
+/// The same color everywhere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolidPattern {
+    props: PatternProps,
+    color: Color,
+}
+
+impl SolidPattern {
+    pub fn new(color: Color) -> Self {
+        Self {
+            props: PatternProps::default(),
+            color,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+impl Pattern for SolidPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Paints each point with a color made from its own coordinates, which
+    /// makes it easy to see which space the point ended up in.
+    #[derive(Debug, Default)]
+    pub(crate) struct TestPattern {
+        props: PatternProps,
+    }
+
+    pub(crate) fn test_pattern() -> TestPattern {
+        TestPattern::default()
+    }
+
+    impl Pattern for TestPattern {
+        fn props(&self) -> &PatternProps {
+            &self.props
+        }
+
+        fn props_mut(&mut self) -> &mut PatternProps {
+            &mut self.props
+        }
+
+        fn local_color_at(&self, point: Point) -> Color {
+            color(point.x(), point.y(), point.z())
+        }
+    }
+
+    #[test]
+    fn the_default_pattern_transformation() {
+        let pattern = test_pattern();
+        assert_eq!(pattern.transform(), identity());
+    }
+
+    #[test]
+    fn assigning_a_transformation() {
+        let pattern = test_pattern().with_transform(translation(1, 2, 3));
+        assert_eq!(pattern.transform(), translation(1, 2, 3));
+    }
+
+    #[test]
+    fn a_pattern_with_an_object_transformation() {
+        let shape = sphere().with_transform(scaling(2, 2, 2));
+        let pattern = test_pattern();
+        let c = pattern.color_at_shape(&shape, point(2, 3, 4));
+        assert_eq!(c, color(1, 1.5, 2));
+    }
+
+    #[test]
+    fn a_pattern_with_a_pattern_transformation() {
+        let shape = sphere();
+        let pattern = test_pattern().with_transform(scaling(2, 2, 2));
+        let c = pattern.color_at_shape(&shape, point(2, 3, 4));
+        assert_eq!(c, color(1, 1.5, 2));
+    }
+
+    #[test]
+    fn a_pattern_with_both_an_object_and_a_pattern_transformation() {
+        let shape = sphere().with_transform(scaling(2, 2, 2));
+        let pattern = test_pattern().with_transform(translation(0.5, 1, 1.5));
+        let c = pattern.color_at_shape(&shape, point(2.5, 3, 3.5));
+        assert_eq!(c, color(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn a_pattern_on_a_grouped_shape_uses_the_whole_parent_chain() {
+        let mut g = group().with_transform(scaling(2, 2, 2));
+        g.add_child(sphere().with_transform(translation(5, 0, 0)));
+        let pattern = test_pattern();
+        let c = pattern.color_at_shape(g.children()[0].as_ref(), point(12, 2, 4));
+        assert_eq!(c, color(1, 1, 2));
+    }
+
+    #[test]
+    fn a_solid_pattern_is_the_same_everywhere() {
+        let pattern = solid_pattern(color(0.2, 0.4, 0.6));
+        assert_eq!(pattern.color_at(point(0, 0, 0)), color(0.2, 0.4, 0.6));
+        assert_eq!(pattern.color_at(point(-3, 7, 1.5)), color(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn colors_convert_into_solid_patterns() {
+        let pattern = color(1, 0, 0).into_pattern();
+        assert_eq!(pattern.color_at(point(4, 5, 6)), color(1, 0, 0));
+    }
+}
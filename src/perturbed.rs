@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn perturbed_pattern(pattern: impl IntoPattern, noise: Noise) -> PerturbedPattern {
+    PerturbedPattern::new(pattern, noise)
+}
+
+/// Jitters the point fed into another pattern with noise, so that straight
+/// stripes and rings come out wavy and organic.
+#[derive(Clone, Debug)]
+pub struct PerturbedPattern {
+    props: PatternProps,
+    pattern: Arc<dyn Pattern>,
+    noise: Noise,
+    scale: Num,
+    octaves: usize,
+}
+
+impl PerturbedPattern {
+    pub fn new(pattern: impl IntoPattern, noise: Noise) -> Self {
+        Self {
+            props: PatternProps::default(),
+            pattern: pattern.into_pattern(),
+            noise,
+            scale: 0.2,
+            octaves: 1,
+        }
+    }
+
+    /// How far, at most, a point may be moved along each axis.
+    pub fn with_scale(mut self, scale: impl Into<Num>) -> Self {
+        self.scale = scale.into();
+        self
+    }
+
+    /// More than one octave uses fractal noise, which adds finer detail to
+    /// the jitter.
+    pub fn with_octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl Pattern for PerturbedPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        // sample the noise at well separated points so that the three axes
+        // are jittered independently
+        let jitter = |offset: Vector| self.noise.fbm(point.add_vector(offset), self.octaves);
+        let dx = jitter(vector(0, 0, 0));
+        let dy = jitter(vector(31.7, 11.3, 5.9));
+        let dz = jitter(vector(-7.1, 23.9, 47.3));
+        let moved = point.add_vector(vector(dx, dy, dz).mul_scalar(self.scale));
+        self.pattern.color_at(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::tests::test_pattern;
+
+    #[test]
+    fn a_perturbed_pattern_moves_points_by_at_most_its_scale() {
+        let pattern = perturbed_pattern(test_pattern(), noise(1)).with_scale(0.5);
+        let mut moved = false;
+        for i in 0..100 {
+            let i = i as Num;
+            let p = point(i * 0.31, i * -0.17, i * 0.07 + 0.5);
+            let c = pattern.color_at(p);
+            let dx = (c.r() - p.x()).abs();
+            let dy = (c.g() - p.y()).abs();
+            let dz = (c.b() - p.z()).abs();
+            assert!(dx <= 0.5 && dy <= 0.5 && dz <= 0.5);
+            moved |= dx > 0.01;
+        }
+        assert!(moved);
+    }
+
+    #[test]
+    fn a_perturbed_pattern_with_no_scale_is_unchanged() {
+        let stripes = stripe_pattern(color(1, 1, 1), color(0, 0, 0));
+        let pattern = perturbed_pattern(stripes.clone(), noise(1)).with_scale(0);
+        for x in [-1.5, -0.25, 0.25, 0.75, 1.25] {
+            let p = point(x, 0.3, 0.6);
+            assert_eq!(pattern.color_at(p), stripes.color_at(p));
+        }
+    }
+
+    #[test]
+    fn a_perturbed_pattern_is_deterministic() {
+        let stripes = stripe_pattern(color(1, 1, 1), color(0, 0, 0));
+        let p1 = perturbed_pattern(stripes.clone(), noise(3)).with_octaves(4);
+        let p2 = perturbed_pattern(stripes, noise(3)).with_octaves(4);
+        for i in 0..50 {
+            let p = point(i as Num * 0.13, 0.4, -0.2);
+            assert_eq!(p1.color_at(p), p2.color_at(p));
+        }
+    }
+}
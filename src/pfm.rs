@@ -0,0 +1,47 @@
+//! Portable Float Map output, which stores every channel as a 32 bit float
+//! so that values above 1 or below 0 survive.
+
+use super::prelude::*;
+
+/// A color (PF) float map. The scale in the header is negative to mark the
+/// samples as little endian, and rows are written from the bottom up.
+pub fn encode_pfm(canvas: &Canvas) -> Vec<u8> {
+    let mut buf = format!("PF\n{} {}\n-1.0\n", canvas.width, canvas.height).into_bytes();
+    for y in (0..canvas.height).rev() {
+        for x in 0..canvas.width {
+            let c = canvas.at(x, y);
+            for v in [c.r(), c.g(), c.b()] {
+                buf.extend((v as f32).to_le_bytes());
+            }
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructing_the_pfm_header() {
+        let pfm = canvas(5, 3).pfm();
+        assert!(pfm.starts_with(b"PF\n5 3\n-1.0\n"));
+        assert_eq!(pfm.len(), 12 + 5 * 3 * 3 * 4);
+    }
+
+    #[test]
+    fn pfm_keeps_values_outside_the_displayable_range() {
+        let mut c = canvas(2, 2);
+        c.write(0, 0, color(1.5, 0, 0));
+        c.write(1, 1, color(-0.5, 0, 1));
+        c.write(0, 1, color(0.25, 1000, 0.5));
+        let pfm = c.pfm();
+        let floats = pfm[12..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect_vec();
+        // the bottom row comes first
+        assert_eq!(floats[..6], [0.25, 1000.0, 0.5, -0.5, 0.0, 1.0]);
+        assert_eq!(floats[6..], [1.5, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,91 @@
+use super::prelude::*;
+
+pub fn plane() -> Plane {
+    Plane::default()
+}
+
+/// An infinite plane through the origin spanning x and z.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Plane {
+    props: ShapeProps,
+}
+
+impl Shape for Plane {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        if ray.dir().y().abs() < EPSILON {
+            // parallel or coplanar rays never cross the plane
+            return Intersections::default();
+        }
+        let t = -ray.origin().y() / ray.dir().y();
+        intersections([intersection(t, self)])
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        bounds(
+            point(Num::NEG_INFINITY, 0, Num::NEG_INFINITY),
+            point(Num::INFINITY, 0, Num::INFINITY),
+        )
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        vector(0, 1, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_normal_of_a_plane_is_constant_everywhere() {
+        let p = plane();
+        assert_eq!(p.local_normal_at(point(0, 0, 0)), vector(0, 1, 0));
+        assert_eq!(p.local_normal_at(point(10, 0, -10)), vector(0, 1, 0));
+        assert_eq!(p.local_normal_at(point(-5, 0, 150)), vector(0, 1, 0));
+    }
+
+    #[test]
+    fn intersect_with_a_ray_parallel_to_the_plane() {
+        let p = plane();
+        let r = ray(point(0, 10, 0), vector(0, 0, 1));
+        assert!(p.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn intersect_with_a_coplanar_ray() {
+        let p = plane();
+        let r = ray(point(0, 0, 0), vector(0, 0, 1));
+        assert!(p.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_intersecting_a_plane_from_above() {
+        let p = plane();
+        let r = ray(point(0, 1, 0), vector(0, -1, 0));
+        let xs = p.local_intersect(r);
+        assert_eq!(xs, intersections([intersection(1, &p)]));
+    }
+
+    #[test]
+    fn a_ray_intersecting_a_plane_from_below() {
+        let p = plane();
+        let r = ray(point(0, -1, 0), vector(0, 1, 0));
+        let xs = p.local_intersect(r);
+        assert_eq!(xs, intersections([intersection(1, &p)]));
+    }
+
+    #[test]
+    fn a_plane_has_a_bounding_box() {
+        let b = plane().local_bounds();
+        assert_eq!(b.min(), point(Num::NEG_INFINITY, 0, Num::NEG_INFINITY));
+        assert_eq!(b.max(), point(Num::INFINITY, 0, Num::INFINITY));
+    }
+}
@@ -0,0 +1,353 @@
+//! PNG export and import, using the pure Rust `png` crate so that nothing
+//! needs to be installed on the system.
+
+use std::{error, fmt, fs, io, path::Path};
+
+use super::prelude::*;
+
+pub fn png_options() -> PngOptions {
+    PngOptions::default()
+}
+
+pub fn encode_png(canvas: &Canvas, options: &PngOptions) -> Result<Vec<u8>, PngError> {
+    let (width, height) = dimensions(canvas)?;
+    let mut buf = vec![];
+    let mut encoder = ::png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(::png::ColorType::Rgb);
+    let encoding = options.output.encoding();
+    if options.srgb || encoding == Encoding::Srgb {
+        encoder.set_source_srgb(::png::SrgbRenderingIntent::Perceptual);
+    }
+    let gamma = match encoding {
+        Encoding::Gamma(gamma) => options.gamma.or(Some(1.0 / gamma)),
+        _ => options.gamma,
+    };
+    if let Some(gamma) = gamma {
+        encoder.set_source_gamma(::png::ScaledFloat::new(gamma as f32));
+    }
+    let samples = (0..canvas.height)
+        .flat_map(|y| (0..canvas.width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let c = options.output.apply(canvas.at(x, y));
+            [c.r(), c.g(), c.b()]
+        });
+    let data: Vec<u8> = match options.bit_depth {
+        PngBitDepth::Eight => {
+            encoder.set_depth(::png::BitDepth::Eight);
+            samples.map(|v| quantize(v, u8::MAX) as u8).collect()
+        }
+        PngBitDepth::Sixteen => {
+            encoder.set_depth(::png::BitDepth::Sixteen);
+            samples
+                .flat_map(|v| (quantize(v, u16::MAX) as u16).to_be_bytes())
+                .collect()
+        }
+    };
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(buf)
+}
+
+/// Reads a PNG of any color type and bit depth. Palettes and low bit
+/// depths are expanded, gray images become gray colors, and alpha is
+/// dropped since a canvas has nowhere to keep it.
+pub fn decode_png(data: &[u8]) -> Result<Canvas, PngError> {
+    let mut decoder = ::png::Decoder::new(data);
+    decoder.set_transformations(::png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    let samples: Vec<Num> = match info.bit_depth {
+        ::png::BitDepth::Sixteen => buf[..info.buffer_size()]
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as Num / u16::MAX as Num)
+            .collect(),
+        _ => buf[..info.buffer_size()]
+            .iter()
+            .map(|&b| b as Num / u8::MAX as Num)
+            .collect(),
+    };
+    let channels = info.color_type.samples();
+    let mut canvas = canvas(width, height);
+    for (i, pixel) in samples.chunks_exact(channels).enumerate() {
+        let c = match info.color_type {
+            ::png::ColorType::Grayscale | ::png::ColorType::GrayscaleAlpha => {
+                color(pixel[0], pixel[0], pixel[0])
+            }
+            _ => color(pixel[0], pixel[1], pixel[2]),
+        };
+        canvas.write(i % width, i / width, c);
+    }
+    Ok(canvas)
+}
+
+pub fn read_png_file(path: impl AsRef<Path>) -> Result<Canvas, PngError> {
+    decode_png(&fs::read(path)?)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngBitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// How a canvas is written out as a PNG. Pixels are put through the output
+/// transform and then clamped to between 0 and 1. The color chunks only
+/// label the data for viewers, and are added to match the transform's
+/// encoding when it isn't linear.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PngOptions {
+    bit_depth: PngBitDepth,
+    srgb: bool,
+    gamma: Option<Num>,
+    output: OutputTransform,
+}
+
+impl PngOptions {
+    pub fn with_bit_depth(mut self, bit_depth: PngBitDepth) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    /// Adds an sRGB chunk, saying that the data is already encoded for
+    /// display in the sRGB color space.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Adds a gAMA chunk with the gamma the data was encoded with, e.g.
+    /// 1/2.2 for data that has been raised to the power of 1/2.2.
+    pub fn with_gamma(mut self, gamma: impl Into<Num>) -> Self {
+        self.gamma = Some(gamma.into());
+        self
+    }
+
+    pub fn with_output_transform(mut self, output: OutputTransform) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn output_transform(&self) -> OutputTransform {
+        self.output
+    }
+
+    pub fn bit_depth(&self) -> PngBitDepth {
+        self.bit_depth
+    }
+}
+
+#[derive(Debug)]
+pub enum PngError {
+    Io(io::Error),
+    Encoding(::png::EncodingError),
+    Decoding(::png::DecodingError),
+    /// PNG dimensions are limited to 32 bits, and can't be zero.
+    InvalidSize {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::Io(err) => write!(f, "png i/o error: {err}"),
+            PngError::Encoding(err) => write!(f, "could not encode png: {err}"),
+            PngError::Decoding(err) => write!(f, "could not decode png: {err}"),
+            PngError::InvalidSize { width, height } => {
+                write!(f, "a {width}x{height} image can't be stored as a png")
+            }
+        }
+    }
+}
+
+impl error::Error for PngError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PngError::Io(err) => Some(err),
+            PngError::Encoding(err) => Some(err),
+            PngError::Decoding(err) => Some(err),
+            PngError::InvalidSize { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for PngError {
+    fn from(value: io::Error) -> Self {
+        PngError::Io(value)
+    }
+}
+
+impl From<::png::EncodingError> for PngError {
+    fn from(value: ::png::EncodingError) -> Self {
+        PngError::Encoding(value)
+    }
+}
+
+impl From<::png::DecodingError> for PngError {
+    fn from(value: ::png::DecodingError) -> Self {
+        PngError::Decoding(value)
+    }
+}
+
+fn dimensions(canvas: &Canvas) -> Result<(u32, u32), PngError> {
+    let (width, height) = (canvas.width, canvas.height);
+    match (u32::try_from(width), u32::try_from(height)) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(PngError::InvalidSize { width, height }),
+    }
+}
+
+/// Scales a channel to an integer between 0 and `max`, rounding the same
+/// way as the PPM writer.
+fn quantize(v: Num, max: impl Into<Num>) -> Num {
+    let max = max.into();
+    (v * max).round().clamp(0.0, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image() -> Canvas {
+        let mut c = canvas(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                let (fx, fy) = (x as Num / 6.0, y as Num / 4.0);
+                c.write(x, y, color(fx, fy, 1.0 - fx * fy));
+            }
+        }
+        c.write(0, 0, color(1.5, -0.5, 0.123456));
+        c
+    }
+
+    fn has_chunk(png: &[u8], name: &[u8]) -> bool {
+        png.windows(4).any(|w| w == name)
+    }
+
+    #[test]
+    fn an_eight_bit_png_reads_back_like_a_ppm() {
+        let c = test_image();
+        let png = c.png().unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let from_png = Canvas::from_png(&png).unwrap();
+        let from_ppm = Canvas::from_ppm(&c.ppm()).unwrap();
+        assert_eq!(from_png, from_ppm);
+    }
+
+    #[test]
+    fn a_sixteen_bit_png_keeps_more_precision() {
+        let c = test_image();
+        let options = png_options().with_bit_depth(PngBitDepth::Sixteen);
+        let png = c.png_with(&options).unwrap();
+        let read = Canvas::from_png(&png).unwrap();
+        assert_eq!(read.at(0, 0), color(1, 0, 0.123456));
+        assert_eq!(read.at(3, 2), c.at(3, 2));
+        assert_eq!(read.at(6, 4), c.at(6, 4));
+        // which eight bits can't do
+        let eight = Canvas::from_png(&c.png().unwrap()).unwrap();
+        assert_ne!(eight.at(0, 0), color(1, 0, 0.123456));
+    }
+
+    #[test]
+    fn color_chunks_are_only_written_when_asked_for() {
+        let c = test_image();
+        let plain = c.png().unwrap();
+        assert!(!has_chunk(&plain, b"sRGB"));
+        assert!(!has_chunk(&plain, b"gAMA"));
+        let srgb = c.png_with(&png_options().with_srgb(true)).unwrap();
+        assert!(has_chunk(&srgb, b"sRGB"));
+        let gamma = c.png_with(&png_options().with_gamma(1.0 / 2.2)).unwrap();
+        assert!(has_chunk(&gamma, b"gAMA"));
+        assert!(!has_chunk(&gamma, b"sRGB"));
+        // the chunks don't change the pixels
+        assert_eq!(
+            Canvas::from_png(&gamma).unwrap(),
+            Canvas::from_png(&plain).unwrap()
+        );
+    }
+
+    #[test]
+    fn the_output_transform_is_applied_on_export() {
+        let c = test_image();
+        let srgb = output_transform()
+            .with_tone_map(ToneMap::Aces)
+            .with_encoding(Encoding::Srgb);
+        let png = c
+            .png_with(&png_options().with_output_transform(srgb))
+            .unwrap();
+        assert!(has_chunk(&png, b"sRGB"));
+        let read = Canvas::from_png(&png).unwrap();
+        let expected = Canvas::from_ppm(&c.ppm_with(&srgb)).unwrap();
+        assert_eq!(read, expected);
+        assert_ne!(read, Canvas::from_png(&c.png().unwrap()).unwrap());
+
+        let gamma = output_transform().with_encoding(Encoding::Gamma(2.2));
+        let png = c
+            .png_with(&png_options().with_output_transform(gamma))
+            .unwrap();
+        assert!(has_chunk(&png, b"gAMA"));
+        assert!(!has_chunk(&png, b"sRGB"));
+    }
+
+    #[test]
+    fn reading_gray_and_paletted_pngs() {
+        let mut gray = vec![];
+        let mut encoder = ::png::Encoder::new(&mut gray, 2, 1);
+        encoder.set_color(::png::ColorType::GrayscaleAlpha);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0, 255, 51, 0]).unwrap();
+        writer.finish().unwrap();
+        let c = Canvas::from_png(&gray).unwrap();
+        assert_eq!(c.at(0, 0), color(0, 0, 0));
+        assert_eq!(c.at(1, 0), color(0.2, 0.2, 0.2));
+
+        let mut indexed = vec![];
+        let mut encoder = ::png::Encoder::new(&mut indexed, 3, 1);
+        encoder.set_color(::png::ColorType::Indexed);
+        encoder.set_depth(::png::BitDepth::Two);
+        encoder.set_palette(vec![255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0b10_01_00_00]).unwrap();
+        writer.finish().unwrap();
+        let c = Canvas::from_png(&indexed).unwrap();
+        assert_eq!(c.at(0, 0), color(0, 0, 1));
+        assert_eq!(c.at(1, 0), color(0, 1, 0));
+        assert_eq!(c.at(2, 0), color(1, 0, 0));
+    }
+
+    #[test]
+    fn reading_an_invalid_png_is_an_error() {
+        let err = Canvas::from_png(b"\x89PNG\r\n\x1a\nnot really").unwrap_err();
+        assert!(matches!(err, PngError::Decoding(_)));
+        let mut png = test_image().png().unwrap();
+        png.truncate(png.len() / 2);
+        assert!(Canvas::from_png(&png).is_err());
+    }
+
+    #[test]
+    fn an_empty_canvas_cannot_be_a_png() {
+        let err = canvas(0, 3).png().unwrap_err();
+        assert!(matches!(
+            err,
+            PngError::InvalidSize {
+                width: 0,
+                height: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn writing_and_reading_a_png_file() {
+        let path = std::env::temp_dir().join(format!("rustracer-{}.png", std::process::id()));
+        let c = test_image();
+        c.write_png_file(&path).unwrap();
+        let read = Canvas::from_png_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, Canvas::from_png(&c.png().unwrap()).unwrap());
+    }
+}
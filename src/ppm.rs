@@ -0,0 +1,329 @@
+//! A reader for PPM images, in both the plain (P3) format, where every
+//! sample is written out as decimal text, and the binary (P6) format, where
+//! samples are raw bytes. Comments are allowed wherever whitespace is in the
+//! header, and in the pixel data of plain images.
+
+use std::{error, fmt, fs, io, path::Path};
+
+use super::prelude::*;
+
+pub fn parse_ppm(data: &[u8]) -> Result<Canvas, PpmError> {
+    Parser { data, pos: 0 }.parse()
+}
+
+pub fn read_ppm_file(path: impl AsRef<Path>) -> Result<Canvas, PpmError> {
+    parse_ppm(&fs::read(path)?)
+}
+
+#[derive(Debug)]
+pub enum PpmError {
+    Io(io::Error),
+    /// The file doesn't start with P3 or P6.
+    UnsupportedFormat(String),
+    /// A header field is missing or isn't a number.
+    InvalidHeader {
+        field: &'static str,
+        value: String,
+    },
+    /// The max color value must be between 1 and 65535.
+    InvalidMaxColor(usize),
+    /// The header declares more pixels than the file could possibly hold.
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+    /// A plain sample isn't a number, or is above the max color value.
+    InvalidSample {
+        index: usize,
+        value: String,
+    },
+    /// The file ended after `found` of the `expected` samples.
+    Truncated {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for PpmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PpmError::Io(err) => write!(f, "could not read ppm file: {err}"),
+            PpmError::UnsupportedFormat(magic) => {
+                write!(f, "unsupported ppm magic number {magic:?}")
+            }
+            PpmError::InvalidHeader { field, value } if value.is_empty() => {
+                write!(f, "ppm ended before {field}")
+            }
+            PpmError::InvalidHeader { field, value } => write!(f, "invalid {field} {value:?}"),
+            PpmError::InvalidMaxColor(max) => {
+                write!(f, "max color value {max} is not between 1 and 65535")
+            }
+            PpmError::TooLarge { width, height } => {
+                write!(f, "a {width}x{height} image is too large")
+            }
+            PpmError::InvalidSample { index, value } => {
+                write!(f, "invalid sample {value:?} at index {index}")
+            }
+            PpmError::Truncated { expected, found } => {
+                write!(f, "expected {expected} samples but found {found}")
+            }
+        }
+    }
+}
+
+impl error::Error for PpmError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PpmError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PpmError {
+    fn from(value: io::Error) -> Self {
+        PpmError::Io(value)
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Canvas, PpmError> {
+        let binary = match self.token() {
+            Some(b"P3") => false,
+            Some(b"P6") => true,
+            magic => {
+                let magic = String::from_utf8_lossy(magic.unwrap_or_default());
+                return Err(PpmError::UnsupportedFormat(magic.into_owned()));
+            }
+        };
+        let width = self.header("width")?;
+        let height = self.header("height")?;
+        let max_color = self.header("max color value")?;
+        if !(1..=65535).contains(&max_color) {
+            return Err(PpmError::InvalidMaxColor(max_color));
+        }
+        // every sample takes up at least one byte, so this rules out
+        // allocating a huge canvas for a tiny file
+        let expected = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .filter(|&n| n <= self.data.len())
+            .ok_or(PpmError::TooLarge { width, height })?;
+        let samples = if binary {
+            // a single whitespace byte separates the header from the data
+            self.pos += 1;
+            self.binary_samples(expected, max_color)?
+        } else {
+            self.plain_samples(expected, max_color)?
+        };
+        let scale = |v: usize| v as Num / max_color as Num;
+        let mut canvas = canvas(width, height);
+        for (i, rgb) in samples.chunks_exact(3).enumerate() {
+            let c = color(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]));
+            canvas.write(i % width, i / width, c);
+        }
+        Ok(canvas)
+    }
+
+    fn header(&mut self, field: &'static str) -> Result<usize, PpmError> {
+        let token = self.token().unwrap_or_default();
+        parse_num(token).ok_or_else(|| PpmError::InvalidHeader {
+            field,
+            value: String::from_utf8_lossy(token).into_owned(),
+        })
+    }
+
+    fn plain_samples(&mut self, expected: usize, max_color: usize) -> Result<Vec<usize>, PpmError> {
+        let mut samples = Vec::with_capacity(expected);
+        while samples.len() < expected {
+            let Some(token) = self.token() else {
+                return Err(PpmError::Truncated {
+                    expected,
+                    found: samples.len(),
+                });
+            };
+            match parse_num(token).filter(|&v| v <= max_color) {
+                Some(v) => samples.push(v),
+                None => {
+                    return Err(PpmError::InvalidSample {
+                        index: samples.len(),
+                        value: String::from_utf8_lossy(token).into_owned(),
+                    })
+                }
+            }
+        }
+        Ok(samples)
+    }
+
+    fn binary_samples(&self, expected: usize, max_color: usize) -> Result<Vec<usize>, PpmError> {
+        let data = self.data.get(self.pos..).unwrap_or_default();
+        // samples above 255 take two bytes, most significant first
+        let samples: Vec<usize> = if max_color < 256 {
+            data.iter().take(expected).map(|&b| b as usize).collect()
+        } else {
+            data.chunks_exact(2)
+                .take(expected)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                .collect()
+        };
+        if samples.len() < expected {
+            return Err(PpmError::Truncated {
+                expected,
+                found: samples.len(),
+            });
+        }
+        if let Some(index) = samples.iter().position(|&v| v > max_color) {
+            return Err(PpmError::InvalidSample {
+                index,
+                value: samples[index].to_string(),
+            });
+        }
+        Ok(samples)
+    }
+
+    /// The next run of non-whitespace bytes, skipping comments.
+    fn token(&mut self) -> Option<&[u8]> {
+        loop {
+            match self.data.get(self.pos)? {
+                b'#' => {
+                    while self.data.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|&b| !b.is_ascii_whitespace() && b != b'#')
+        {
+            self.pos += 1;
+        }
+        Some(&self.data[start..self.pos])
+    }
+}
+
+fn parse_num(token: &[u8]) -> Option<usize> {
+    std::str::from_utf8(token).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p6(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut ppm = header.as_bytes().to_vec();
+        ppm.extend_from_slice(data);
+        ppm
+    }
+
+    #[test]
+    fn reading_a_binary_ppm() {
+        let ppm = p6("P6\n2 1\n255\n", &[255, 0, 51, 0, 102, 255]);
+        let c = parse_ppm(&ppm).unwrap();
+        assert_eq!(c.at(0, 0), color(1, 0, 0.2));
+        assert_eq!(c.at(1, 0), color(0, 0.4, 1));
+    }
+
+    #[test]
+    fn binary_data_may_start_with_whitespace_bytes() {
+        // the byte after the max color value is always the separator, so
+        // a first sample of 10 (a newline) must not be skipped
+        let ppm = p6("P6 # a comment\n1 1 10 ", &[10, 5, 0]);
+        let c = parse_ppm(&ppm).unwrap();
+        assert_eq!(c.at(0, 0), color(1, 0.5, 0));
+    }
+
+    #[test]
+    fn reading_a_sixteen_bit_binary_ppm() {
+        let ppm = p6("P6\n1 1\n1000\n", &[3, 232, 1, 244, 0, 0]);
+        let c = parse_ppm(&ppm).unwrap();
+        assert_eq!(c.at(0, 0), color(1, 0.5, 0));
+    }
+
+    #[test]
+    fn binary_output_round_trips() {
+        let mut c = canvas(3, 2);
+        c.write(0, 0, color(1, 0, 0));
+        c.write(2, 1, color(0.2, 0.4, 0.6));
+        c.write(1, 1, color(1.5, -0.5, 0.5));
+        let ppm = c.ppm_binary();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 3 * 2 * 3);
+        let read = parse_ppm(&ppm).unwrap();
+        assert_eq!(read.at(0, 0), color(1, 0, 0));
+        assert_eq!(read.at(2, 1), color(0.2, 0.4, 0.6));
+        assert_eq!(read.at(1, 1), color(1, 0, 0.50196));
+    }
+
+    #[test]
+    fn plain_and_binary_output_read_back_the_same() {
+        let mut c = canvas(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                c.write(x, y, color(x as Num / 3.0, y as Num / 3.0, 0.3));
+            }
+        }
+        assert_eq!(
+            parse_ppm(&c.ppm()).unwrap(),
+            parse_ppm(&c.ppm_binary()).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_headers_are_reported() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"", "unsupported ppm magic number \"\""),
+            (b"P5\n1 1\n255\n", "unsupported ppm magic number \"P5\""),
+            (b"P3\n1", "ppm ended before height"),
+            (b"P3\nx 1\n255\n", "invalid width \"x\""),
+            (
+                b"P3\n1 1\n0\n0 0 0",
+                "max color value 0 is not between 1 and 65535",
+            ),
+            (b"P6\n100 100\n255\n", "a 100x100 image is too large"),
+        ];
+        for (ppm, message) in cases {
+            let err = parse_ppm(ppm).unwrap_err();
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn malformed_pixel_data_is_reported() {
+        let err = parse_ppm(b"P3\n2 1\n255\n0 0 0 0 0\n").unwrap_err();
+        assert!(matches!(
+            err,
+            PpmError::Truncated {
+                expected: 6,
+                found: 5
+            }
+        ));
+        let err = parse_ppm(b"P3\n1 1\n100\n0 101 0\n").unwrap_err();
+        assert!(matches!(err, PpmError::InvalidSample { index: 1, .. }));
+        let err = parse_ppm(b"P3\n1 1\n100\n0 -1 0\n").unwrap_err();
+        assert!(matches!(err, PpmError::InvalidSample { index: 1, .. }));
+        let err = parse_ppm(&p6("P6\n2 1\n255\n", &[1, 2, 3, 4])).unwrap_err();
+        assert!(matches!(
+            err,
+            PpmError::Truncated {
+                expected: 6,
+                found: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn reading_a_missing_file() {
+        let err = read_ppm_file("/nonexistent/image.ppm").unwrap_err();
+        assert!(matches!(err, PpmError::Io(_)));
+    }
+}
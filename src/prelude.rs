@@ -1,10 +1,48 @@
+pub use crate::blend::*;
+pub use crate::bounds::*;
+pub use crate::bump::*;
+pub use crate::camera::*;
 pub use crate::canvas::*;
+pub use crate::checker::*;
 pub use crate::color::*;
+pub use crate::cone::*;
 pub use crate::coord::*;
+pub use crate::csg::*;
+pub use crate::cube::*;
+pub use crate::cylinder::*;
+pub use crate::draw::*;
+pub use crate::exr::*;
+pub use crate::gradient::*;
+pub use crate::group::*;
 pub use crate::intersection::*;
+pub use crate::light::*;
+pub use crate::marble::*;
+pub use crate::material::*;
 pub use crate::matrix::*;
+pub use crate::noise::*;
+pub use crate::obj::*;
+pub use crate::pattern::*;
+pub use crate::perturbed::*;
+pub use crate::pfm::*;
+pub use crate::plane::*;
+pub use crate::png::*;
+pub use crate::ppm::*;
 pub use crate::rays::*;
+pub use crate::region::*;
+pub use crate::render::*;
+pub use crate::ring::*;
+pub use crate::sampler::*;
+pub use crate::shape::*;
+pub use crate::smooth_triangle::*;
 pub use crate::sphere::*;
+pub use crate::stats::*;
+pub use crate::stripe::*;
+pub use crate::texture::*;
+pub use crate::tonemap::*;
 pub use crate::transform::*;
+pub use crate::triangle::*;
+pub use crate::uv::*;
+pub use crate::wood::*;
+pub use crate::world::*;
 
 pub use itertools::Itertools;
@@ -0,0 +1,371 @@
+//! Working with parts of a canvas: views of a rectangle, copying pixels
+//! between canvases, and making cropped, flipped and resized copies.
+
+use super::prelude::*;
+
+pub fn rect(x: usize, y: usize, width: usize, height: usize) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// A rectangle of pixels, with its top left corner at (x, y).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.right()).contains(&x) && (self.y..self.bottom()).contains(&y)
+    }
+
+    /// Whether the rectangle fits inside a canvas of the given size.
+    pub fn fits(&self, width: usize, height: usize) -> bool {
+        self.right() <= width && self.bottom() <= height
+    }
+
+    fn right(&self) -> usize {
+        self.x.saturating_add(self.width)
+    }
+
+    fn bottom(&self) -> usize {
+        self.y.saturating_add(self.height)
+    }
+
+    /// The part of this rectangle that is also in `other`, which may be
+    /// empty.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        rect(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
+/// How pixels are picked or blended when a canvas is resized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// The closest source pixel, which keeps hard edges.
+    #[default]
+    Nearest,
+    /// A blend of the four closest source pixels, for smooth enlargements.
+    Bilinear,
+    /// The average of every source pixel covered by the new pixel, weighted
+    /// by how much of it is covered, which avoids aliasing when shrinking.
+    Area,
+}
+
+/// A read only window onto a rectangle of a canvas, with its own
+/// coordinates starting at the rectangle's top left corner.
+#[derive(Clone, Copy, Debug)]
+pub struct CanvasView<'a> {
+    canvas: &'a Canvas,
+    rect: Rect,
+}
+
+impl<'a> From<&'a Canvas> for CanvasView<'a> {
+    fn from(canvas: &'a Canvas) -> Self {
+        Self {
+            canvas,
+            rect: rect(0, 0, canvas.width, canvas.height),
+        }
+    }
+}
+
+impl CanvasView<'_> {
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn width(&self) -> usize {
+        self.rect.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rect.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Result<Color, CanvasError> {
+        if x >= self.rect.width || y >= self.rect.height {
+            return Err(CanvasError::OutOfBounds {
+                x,
+                y,
+                width: self.rect.width,
+                height: self.rect.height,
+            });
+        }
+        self.canvas.get(self.rect.x + x, self.rect.y + y)
+    }
+
+    /// Panics if the pixel is outside of the view.
+    pub fn at(&self, x: usize, y: usize) -> Color {
+        self.get(x, y).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Every pixel in the view with its coordinates in the view, row by
+    /// row from the top left.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        (0..self.rect.height)
+            .flat_map(move |y| (0..self.rect.width).map(move |x| (x, y, self.at(x, y))))
+    }
+
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = canvas(self.rect.width, self.rect.height);
+        for (x, y, c) in self.pixels() {
+            canvas.write(x, y, c);
+        }
+        canvas
+    }
+}
+
+impl Canvas {
+    pub fn view(&self, rect: Rect) -> Result<CanvasView<'_>, CanvasError> {
+        if !rect.fits(self.width, self.height) {
+            return Err(CanvasError::RegionOutOfBounds {
+                rect,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(CanvasView { canvas: self, rect })
+    }
+
+    pub fn crop(&self, rect: Rect) -> Result<Canvas, CanvasError> {
+        Ok(self.view(rect)?.to_canvas())
+    }
+
+    /// Copies `source` onto this canvas with its top left corner at (x, y).
+    /// Whatever would land outside of this canvas is left out.
+    pub fn blit<'a>(&mut self, source: impl Into<CanvasView<'a>>, x: usize, y: usize) {
+        let source = source.into();
+        let width = source.width().min(self.width.saturating_sub(x));
+        let height = source.height().min(self.height.saturating_sub(y));
+        for sy in 0..height {
+            for sx in 0..width {
+                self.write(x + sx, y + sy, source.at(sx, sy));
+            }
+        }
+    }
+
+    /// A mirror image, with left and right swapped.
+    pub fn flip_horizontal(&self) -> Canvas {
+        let mut flipped = self.clone();
+        for row in flipped.rows_mut() {
+            row.reverse();
+        }
+        flipped
+    }
+
+    /// An upside down copy.
+    pub fn flip_vertical(&self) -> Canvas {
+        let mut flipped = canvas(self.width, self.height);
+        for (row, source) in flipped.rows_mut().zip(self.rows().rev()) {
+            row.copy_from_slice(source);
+        }
+        flipped
+    }
+
+    pub fn resize(&self, width: usize, height: usize, filter: ResizeFilter) -> Canvas {
+        let mut resized = canvas(width, height);
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+        // how many source pixels each new pixel covers
+        let sx = self.width as Num / width as Num;
+        let sy = self.height as Num / height as Num;
+        for (x, y, pixel) in resized.pixels_mut() {
+            *pixel = match filter {
+                ResizeFilter::Nearest => {
+                    let px = ((x as Num + 0.5) * sx) as usize;
+                    let py = ((y as Num + 0.5) * sy) as usize;
+                    self.at(px.min(self.width - 1), py.min(self.height - 1))
+                }
+                ResizeFilter::Bilinear => {
+                    // the source position of the new pixel's center, in
+                    // pixel centers
+                    let fx = ((x as Num + 0.5) * sx - 0.5).clamp(0.0, (self.width - 1) as Num);
+                    let fy = ((y as Num + 0.5) * sy - 0.5).clamp(0.0, (self.height - 1) as Num);
+                    let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+                    let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                    let (tx, ty) = (fx - x0 as Num, fy - y0 as Num);
+                    let lerp = |a: Color, b: Color, t: Num| a + (b - a).mul_scalar(t);
+                    let top = lerp(self.at(x0, y0), self.at(x1, y0), tx);
+                    let bottom = lerp(self.at(x0, y1), self.at(x1, y1), tx);
+                    lerp(top, bottom, ty)
+                }
+                ResizeFilter::Area => {
+                    let xs = coverage(x as Num * sx, (x + 1) as Num * sx, self.width);
+                    let ys = coverage(y as Num * sy, (y + 1) as Num * sy, self.height);
+                    let mut sum = color(0, 0, 0);
+                    for &(py, wy) in &ys {
+                        for &(px, wx) in &xs {
+                            sum = sum + self.at(px, py).mul_scalar(wx * wy);
+                        }
+                    }
+                    sum.mul_scalar(1.0 / (sx * sy))
+                }
+            };
+        }
+        resized
+    }
+}
+
+/// The source pixels that overlap the span from `start` to `end`, with how
+/// much of each one is covered.
+fn coverage(start: Num, end: Num, len: usize) -> Vec<(usize, Num)> {
+    let first = start.floor() as usize;
+    let last = (end.ceil() as usize).min(len);
+    (first..last)
+        .map(|i| {
+            let covered = end.min((i + 1) as Num) - start.max(i as Num);
+            (i, covered)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A canvas where every pixel's red and green are its coordinates.
+    fn coords(width: usize, height: usize) -> Canvas {
+        let mut c = canvas(width, height);
+        for (x, y, pixel) in c.pixels_mut() {
+            *pixel = color(x as Num, y as Num, 0);
+        }
+        c
+    }
+
+    #[test]
+    fn intersecting_rectangles() {
+        let a = rect(0, 0, 4, 4);
+        assert_eq!(a.intersect(&rect(2, 3, 5, 5)), rect(2, 3, 2, 1));
+        assert!(a.intersect(&rect(5, 5, 1, 1)).is_empty());
+        assert!(a.contains(3, 3));
+        assert!(!a.contains(4, 0));
+    }
+
+    #[test]
+    fn a_view_has_its_own_coordinates() {
+        let c = coords(5, 4);
+        let v = c.view(rect(1, 2, 3, 2)).unwrap();
+        assert_eq!(v.width(), 3);
+        assert_eq!(v.at(0, 0), color(1, 2, 0));
+        assert_eq!(v.at(2, 1), color(3, 3, 0));
+        assert!(v.get(3, 0).is_err());
+        assert_eq!(v.pixels().count(), 6);
+    }
+
+    #[test]
+    fn a_view_must_fit_in_the_canvas() {
+        let c = coords(5, 4);
+        let err = c.view(rect(3, 0, 3, 1)).unwrap_err();
+        assert_eq!(
+            err,
+            CanvasError::RegionOutOfBounds {
+                rect: rect(3, 0, 3, 1),
+                width: 5,
+                height: 4
+            }
+        );
+    }
+
+    #[test]
+    fn cropping_a_canvas() {
+        let c = coords(5, 4);
+        let cropped = c.crop(rect(2, 1, 2, 3)).unwrap();
+        assert_eq!((cropped.width, cropped.height), (2, 3));
+        assert_eq!(cropped.at(0, 0), color(2, 1, 0));
+        assert_eq!(cropped.at(1, 2), color(3, 3, 0));
+        assert!(c.crop(rect(0, 0, 6, 1)).is_err());
+    }
+
+    #[test]
+    fn blitting_one_canvas_onto_another() {
+        let mut c = canvas(4, 4);
+        let source = coords(2, 2);
+        c.blit(&source, 1, 1);
+        assert_eq!(c.at(1, 1), color(0, 0, 0));
+        assert_eq!(c.at(2, 2), color(1, 1, 0));
+        assert_eq!(c.at(3, 3), color(0, 0, 0));
+        // a view of part of a canvas, clipped at the edges
+        let mut c = canvas(4, 4);
+        let source = coords(5, 5);
+        c.blit(source.view(rect(1, 1, 4, 4)).unwrap(), 2, 3);
+        assert_eq!(c.at(2, 3), color(1, 1, 0));
+        assert_eq!(c.at(3, 3), color(2, 1, 0));
+        assert_eq!(c.at(1, 3), color(0, 0, 0));
+        assert_eq!(c.at(2, 2), color(0, 0, 0));
+        c.blit(&source, 10, 10);
+    }
+
+    #[test]
+    fn flipping_a_canvas() {
+        let c = coords(3, 2);
+        let h = c.flip_horizontal();
+        assert_eq!(h.at(0, 0), color(2, 0, 0));
+        assert_eq!(h.at(2, 1), color(0, 1, 0));
+        let v = c.flip_vertical();
+        assert_eq!(v.at(0, 0), color(0, 1, 0));
+        assert_eq!(v.at(2, 1), color(2, 0, 0));
+        assert_eq!(h.flip_horizontal(), c);
+    }
+
+    #[test]
+    fn resizing_with_nearest_neighbors() {
+        let c = coords(2, 2);
+        let big = c.resize(4, 4, ResizeFilter::Nearest);
+        assert_eq!(big.at(1, 1), color(0, 0, 0));
+        assert_eq!(big.at(2, 1), color(1, 0, 0));
+        assert_eq!(big.at(3, 3), color(1, 1, 0));
+        let small = coords(4, 4).resize(2, 2, ResizeFilter::Nearest);
+        assert_eq!(small.at(1, 1), color(3, 3, 0));
+    }
+
+    #[test]
+    fn resizing_with_bilinear_filtering() {
+        let c = coords(2, 1);
+        let big = c.resize(4, 1, ResizeFilter::Bilinear);
+        let reds = (0..4).map(|x| big.at(x, 0).r()).collect_vec();
+        assert_eq!(reds, [0.0, 0.25, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn resizing_with_area_averaging() {
+        let c = coords(4, 2);
+        let small = c.resize(2, 1, ResizeFilter::Area);
+        assert_eq!(small.at(0, 0), color(0.5, 0.5, 0));
+        assert_eq!(small.at(1, 0), color(2.5, 0.5, 0));
+        // a size that doesn't divide evenly covers pixels partially
+        let small = coords(3, 1).resize(2, 1, ResizeFilter::Area);
+        assert_eq!(small.at(0, 0), color(1.0 / 3.0, 0, 0));
+        assert_eq!(small.at(1, 0), color(5.0 / 3.0, 0, 0));
+    }
+
+    #[test]
+    fn resizing_keeps_solid_colors() {
+        let mut c = canvas(7, 5);
+        c.fill(color(0.2, 0.4, 0.6));
+        for filter in [
+            ResizeFilter::Nearest,
+            ResizeFilter::Bilinear,
+            ResizeFilter::Area,
+        ] {
+            let resized = c.resize(3, 11, filter);
+            assert!(resized.pixels().all(|(_, _, p)| p == color(0.2, 0.4, 0.6)));
+        }
+        assert_eq!(canvas(0, 0).resize(2, 2, ResizeFilter::Area), canvas(2, 2));
+    }
+}
@@ -0,0 +1,686 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::prelude::*;
+use crate::stats;
+
+pub fn renderer() -> Renderer {
+    Renderer::default()
+}
+
+/// A rectangle of pixels that is rendered as one unit of work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// The coordinates of every pixel in the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// Splits a canvas into tiles of at most `size` by `size` pixels, in rows
+/// from the top left. Tiles along the right and bottom edges are smaller
+/// when the canvas doesn't divide evenly.
+pub fn tiles(width: usize, height: usize, size: usize) -> Vec<Tile> {
+    let size = size.max(1);
+    let mut tiles = vec![];
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            });
+        }
+    }
+    tiles
+}
+
+/// Renders a world on several threads. The canvas is split into tiles that
+/// the threads take turns picking up, so that threads that get cheap tiles
+/// (e.g. empty sky) don't sit idle while others finish.
+///
+/// With the default sampler, every pixel is traced exactly as
+/// `Camera::render` would trace it, so the result is identical to rendering
+/// on a single thread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Renderer {
+    threads: usize,
+    tile_size: usize,
+    sampler: Sampler,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            threads,
+            tile_size: 16,
+            sampler: Sampler::default(),
+        }
+    }
+}
+
+impl Renderer {
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// How many rays are fired for each pixel and how they are combined.
+    /// The default fires one ray through the center of each pixel.
+    pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    pub fn sampler(&self) -> Sampler {
+        self.sampler
+    }
+
+    pub fn render(&self, camera: &Camera, world: &World) -> Canvas {
+        self.render_with(camera, world, &CancelToken::new(), |_| {})
+            .canvas
+    }
+
+    /// Like `render`, but calls `on_progress` on the calling thread every
+    /// time a tile is finished, and stops early if `cancel` is cancelled.
+    pub fn render_with(
+        &self,
+        camera: &Camera,
+        world: &World,
+        cancel: &CancelToken,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Rendered {
+        let (width, height) = (camera.hsize(), camera.vsize());
+        let sampler = self.sampler;
+        let shade = |x, y| {
+            sampler.sample(x, y, |dx, dy| {
+                world.color_at(camera.ray_for_offset(x, y, dx, dy))
+            })
+        };
+        if !sampler.is_adaptive() {
+            return self.run(width, height, cancel, on_progress, shade);
+        }
+        // adaptive sampling compares each pixel with its neighbors, so it
+        // needs a quick pass with one ray per pixel before refining
+        let first = self.run(
+            width,
+            height,
+            cancel,
+            |p| on_progress(&p.then(&p.not_started())),
+            |x, y| world.color_at(camera.ray_for_pixel(x, y)),
+        );
+        if first.cancelled {
+            return Rendered {
+                progress: first.progress.then(&first.progress.not_started()),
+                ..first
+            };
+        }
+        // the refinement pass starts from the coarse image, so that pixels it
+        // doesn't get to before a cancel keep their first pass color
+        let coarse = &first.canvas;
+        let mut second = self.run_onto(
+            coarse.clone(),
+            cancel,
+            |p| on_progress(&first.progress.then(p)),
+            |x, y| {
+                if sampler.needs_refinement(coarse, x, y) {
+                    shade(x, y)
+                } else {
+                    coarse.at(x, y)
+                }
+            },
+        );
+        second.progress = first.progress.then(&second.progress);
+        second
+    }
+
+    /// The render loop itself, which fills a canvas by calling `shade` for
+    /// every pixel. Once `cancel` is cancelled the workers stop within a
+    /// pixel, and the pixels that were finished are returned with the rest
+    /// left black.
+    pub fn run(
+        &self,
+        width: usize,
+        height: usize,
+        cancel: &CancelToken,
+        on_progress: impl FnMut(&Progress),
+        shade: impl Fn(usize, usize) -> Color + Sync,
+    ) -> Rendered {
+        self.run_onto(canvas(width, height), cancel, on_progress, shade)
+    }
+
+    /// `run`, writing the shaded pixels over an existing image instead of
+    /// a black one.
+    fn run_onto(
+        &self,
+        mut image: Canvas,
+        cancel: &CancelToken,
+        mut on_progress: impl FnMut(&Progress),
+        shade: impl Fn(usize, usize) -> Color + Sync,
+    ) -> Rendered {
+        let start = Instant::now();
+        let (width, height) = (image.width, image.height);
+        let tiles = tiles(width, height, self.tile_size);
+        let mut progress = Progress {
+            tiles_done: 0,
+            tiles_total: tiles.len(),
+            pixels_done: 0,
+            pixels_total: width * height,
+            rays: 0,
+            elapsed: Duration::ZERO,
+        };
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel::<Finished>();
+        thread::scope(|scope| {
+            let workers = (0..self.threads.min(tiles.len()))
+                .map(|_| {
+                    let tx = tx.clone();
+                    let (tiles, next, shade) = (&tiles, &next, &shade);
+                    scope.spawn(move || {
+                        let mut rays = 0;
+                        while let Some(&tile) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            let pixels = tile
+                                .pixels()
+                                .take_while(|_| !cancel.is_cancelled())
+                                .map(|(x, y)| shade(x, y))
+                                .collect();
+                            let traced = stats::stats().rays - rays;
+                            rays += traced;
+                            let finished = Finished {
+                                tile,
+                                pixels,
+                                rays: traced,
+                            };
+                            let sent = finished.pixels.is_empty() || tx.send(finished).is_ok();
+                            if !sent || cancel.is_cancelled() {
+                                break;
+                            }
+                        }
+                        stats::take_stats()
+                    })
+                })
+                .collect_vec();
+            // only the workers hold senders now, so the loop below ends
+            // once they are all done
+            drop(tx);
+            for finished in rx {
+                let count = finished.pixels.len();
+                for ((x, y), color) in finished.tile.pixels().zip(finished.pixels) {
+                    image.write(x, y, color);
+                }
+                if count == finished.tile.width * finished.tile.height {
+                    progress.tiles_done += 1;
+                }
+                progress.pixels_done += count;
+                progress.rays += finished.rays;
+                progress.elapsed = start.elapsed();
+                on_progress(&progress);
+            }
+            for worker in workers {
+                stats::merge_stats(worker.join().expect("render worker panicked"));
+            }
+        });
+        progress.elapsed = start.elapsed();
+        Rendered {
+            canvas: image,
+            cancelled: progress.pixels_done < progress.pixels_total,
+            progress,
+        }
+    }
+}
+
+/// The pixels a worker traced for a tile, which may be cut short by
+/// cancellation.
+struct Finished {
+    tile: Tile,
+    pixels: Vec<Color>,
+    rays: u64,
+}
+
+/// What a render produced, which is only part of the image if it was
+/// cancelled.
+#[derive(Clone, Debug)]
+pub struct Rendered {
+    pub canvas: Canvas,
+    pub progress: Progress,
+    pub cancelled: bool,
+}
+
+/// How far along a render is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub pixels_done: usize,
+    pub pixels_total: usize,
+    /// Every ray traced so far, including shadow, reflection and
+    /// refraction rays.
+    pub rays: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// How much of the image is done, from 0 to 1.
+    pub fn fraction(&self) -> Num {
+        if self.pixels_total == 0 {
+            1.0
+        } else {
+            self.pixels_done as Num / self.pixels_total as Num
+        }
+    }
+
+    /// A guess at how much longer the render will take, assuming the rest
+    /// of the image is as expensive as what has been rendered so far.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.pixels_done == 0 {
+            return None;
+        }
+        let remaining = (self.pixels_total - self.pixels_done) as Num / self.pixels_done as Num;
+        Some(self.elapsed.mul_f64(remaining))
+    }
+
+    /// The progress of a render made of this one followed by another.
+    fn then(&self, next: &Progress) -> Progress {
+        Progress {
+            tiles_done: self.tiles_done + next.tiles_done,
+            tiles_total: self.tiles_total + next.tiles_total,
+            pixels_done: self.pixels_done + next.pixels_done,
+            pixels_total: self.pixels_total + next.pixels_total,
+            rays: self.rays + next.rays,
+            elapsed: self.elapsed + next.elapsed,
+        }
+    }
+
+    /// The same amount of work with none of it done.
+    fn not_started(&self) -> Progress {
+        Progress {
+            tiles_done: 0,
+            pixels_done: 0,
+            rays: 0,
+            elapsed: Duration::ZERO,
+            ..*self
+        }
+    }
+
+    pub fn rays_per_sec(&self) -> Num {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.rays as Num / secs
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} tiles ({:.1}%), {:.0} rays/sec",
+            self.tiles_done,
+            self.tiles_total,
+            self.fraction() * 100.0,
+            self.rays_per_sec()
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", eta {:.1}s", eta.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+/// Stops a render from another thread, or from the progress callback.
+/// Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn test_scene() -> (Camera, World) {
+        let w = default_world()
+            .with_object(
+                plane().with_transform(translation(0, -1, 0)).with_material(
+                    material()
+                        .with_pattern(checker_pattern(color(1, 1, 1), color(0, 0, 0)))
+                        .with_reflective(0.3),
+                ),
+            )
+            .with_object(glass_sphere().with_transform(translation(1.5, 0, -1)));
+        let c = camera(37, 23, PI / 3.0).with_transform(view_transform(
+            point(0, 1.5, -5),
+            point(0, 0, 0),
+            vector(0, 1, 0),
+        ));
+        (c, w)
+    }
+
+    #[test]
+    fn tiles_cover_the_canvas_exactly_once() {
+        let mut seen = vec![0; 10 * 7];
+        let ts = tiles(10, 7, 4);
+        assert_eq!(ts.len(), 6);
+        assert_eq!(
+            ts[5],
+            Tile {
+                x: 8,
+                y: 4,
+                width: 2,
+                height: 3
+            }
+        );
+        for tile in ts {
+            for (x, y) in tile.pixels() {
+                seen[y * 10 + x] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn rendering_a_world_in_parallel() {
+        let w = default_world();
+        let c = camera(11, 11, FRAC_PI_2).with_transform(view_transform(
+            point(0, 0, -5),
+            point(0, 0, 0),
+            vector(0, 1, 0),
+        ));
+        let image = renderer().with_threads(4).with_tile_size(3).render(&c, &w);
+        assert_eq!(image.at(5, 5), color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn parallel_renders_are_identical_to_serial_renders() {
+        let (c, w) = test_scene();
+        let serial = c.render(&w);
+        for (threads, tile_size) in [(1, 16), (2, 5), (3, 1), (8, 7), (4, 100)] {
+            let parallel = renderer()
+                .with_threads(threads)
+                .with_tile_size(tile_size)
+                .render(&c, &w);
+            for y in 0..c.vsize() {
+                for x in 0..c.hsize() {
+                    // compare exact bits, not the approximate equality that
+                    // colors normally use
+                    let (a, b) = (serial.at(x, y), parallel.at(x, y));
+                    assert_eq!(
+                        [a.r().to_bits(), a.g().to_bits(), a.b().to_bits()],
+                        [b.r().to_bits(), b.g().to_bits(), b.b().to_bits()],
+                        "pixel {x},{y} with {threads} threads and {tile_size} px tiles"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn render_stats_are_gathered_from_every_worker() {
+        let (c, w) = test_scene();
+        take_stats();
+        c.render(&w);
+        let serial = take_stats();
+        renderer().with_threads(3).with_tile_size(4).render(&c, &w);
+        assert_eq!(take_stats(), serial);
+    }
+
+    #[test]
+    fn progress_is_reported_for_every_tile() {
+        let (c, w) = test_scene();
+        let mut reports = vec![];
+        let rendered = renderer().with_threads(3).with_tile_size(8).render_with(
+            &c,
+            &w,
+            &CancelToken::new(),
+            |p| reports.push(*p),
+        );
+        assert!(!rendered.cancelled);
+        assert_eq!(reports.len(), tiles(37, 23, 8).len());
+        for (i, p) in reports.iter().enumerate() {
+            assert_eq!(p.tiles_done, i + 1);
+        }
+        let last = reports.last().unwrap();
+        assert_eq!(last.pixels_done, 37 * 23);
+        assert_eq!(last.fraction(), 1.0);
+        assert_eq!(last.eta(), Some(Duration::ZERO));
+        assert!(last.rays >= (37 * 23) as u64);
+        assert_eq!(rendered.progress.rays, last.rays);
+        assert_eq!(rendered.canvas, c.render(&w));
+    }
+
+    #[test]
+    fn cancelling_a_render_returns_the_finished_part() {
+        let cancel = CancelToken::new();
+        let shaded = AtomicUsize::new(0);
+        // cancel part way through the third 4x4 tile
+        let rendered = renderer().with_threads(1).with_tile_size(4).run(
+            37,
+            23,
+            &cancel,
+            |_| {},
+            |x, y| {
+                if shaded.fetch_add(1, Ordering::Relaxed) == 39 {
+                    cancel.cancel();
+                }
+                color(1, x as Num, y as Num)
+            },
+        );
+        assert!(rendered.cancelled);
+        assert_eq!(rendered.progress.pixels_done, 40);
+        assert_eq!(rendered.progress.tiles_done, 2);
+        let tiles = tiles(37, 23, 4);
+        let done = tiles[..2].iter().flat_map(Tile::pixels);
+        let partial = tiles[2].pixels().take(8);
+        for (x, y) in done.chain(partial) {
+            assert_eq!(rendered.canvas.at(x, y), color(1, x as Num, y as Num));
+        }
+        assert_eq!(rendered.canvas.at(36, 22), color(0, 0, 0));
+    }
+
+    #[test]
+    fn cancelling_from_the_progress_callback() {
+        let (c, w) = test_scene();
+        let cancel = CancelToken::new();
+        let rendered = renderer().with_threads(2).with_tile_size(4).render_with(
+            &c,
+            &w,
+            &cancel.clone(),
+            |p| {
+                if p.tiles_done == 1 {
+                    cancel.cancel();
+                }
+            },
+        );
+        // the workers may finish a few more tiles before they notice, but
+        // every pixel they finished matches a full render
+        let serial = c.render(&w);
+        let black = color(0, 0, 0);
+        let matching = (0..23)
+            .flat_map(|y| (0..37).map(move |x| (x, y)))
+            .filter(|&(x, y)| rendered.canvas.at(x, y) == serial.at(x, y))
+            .count();
+        assert!(matching >= rendered.progress.pixels_done);
+        for y in 0..23 {
+            for x in 0..37 {
+                let c = rendered.canvas.at(x, y);
+                assert!(c == serial.at(x, y) || c == black);
+            }
+        }
+    }
+
+    #[test]
+    fn a_render_cancelled_up_front_does_no_work() {
+        let (c, w) = test_scene();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let rendered = renderer().render_with(&c, &w, &cancel, |_| {});
+        assert!(rendered.cancelled);
+        assert_eq!(rendered.canvas, canvas(37, 23));
+    }
+
+    #[test]
+    fn the_render_loop_shades_every_pixel() {
+        let rendered = renderer().with_threads(2).with_tile_size(3).run(
+            5,
+            4,
+            &CancelToken::new(),
+            |_| {},
+            |x, y| color(x as Num, y as Num, 0),
+        );
+        assert_eq!(rendered.canvas.at(4, 3), color(4, 3, 0));
+        assert_eq!(rendered.canvas.at(2, 1), color(2, 1, 0));
+    }
+
+    #[test]
+    fn estimating_the_time_left() {
+        let p = Progress {
+            tiles_done: 1,
+            tiles_total: 4,
+            pixels_done: 25,
+            pixels_total: 100,
+            rays: 50,
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(p.fraction(), 0.25);
+        assert_eq!(p.eta(), Some(Duration::from_secs(6)));
+        assert_eq!(p.rays_per_sec(), 25.0);
+        assert_eq!(p.to_string(), "1/4 tiles (25.0%), 25 rays/sec, eta 6.0s");
+    }
+
+    #[test]
+    fn supersampled_renders_do_not_depend_on_the_threads() {
+        let (c, w) = test_scene();
+        let sampler = sampler()
+            .with_supersampling(Supersampling::Jittered(2))
+            .with_filter(Filter::Mitchell);
+        let one = renderer().with_threads(1).with_sampler(sampler);
+        let many = renderer()
+            .with_threads(4)
+            .with_tile_size(5)
+            .with_sampler(sampler);
+        assert_eq!(one.render(&c, &w), many.render(&c, &w));
+    }
+
+    #[test]
+    fn supersampling_fires_several_rays_per_pixel() {
+        let (c, w) = test_scene();
+        let cancel = CancelToken::new();
+        let center = renderer().render_with(&c, &w, &cancel, |_| {});
+        let sampler = sampler().with_supersampling(Supersampling::Grid(3));
+        let grid = renderer()
+            .with_sampler(sampler)
+            .render_with(&c, &w, &cancel, |_| {});
+        assert!(grid.progress.rays > 8 * center.progress.rays);
+        assert_ne!(grid.canvas, center.canvas);
+    }
+
+    #[test]
+    fn adaptive_sampling_only_refines_where_colors_change() {
+        let (c, w) = test_scene();
+        let cancel = CancelToken::new();
+        let center = renderer().render_with(&c, &w, &cancel, |_| {});
+        let sampler = sampler().with_supersampling(Supersampling::Adaptive {
+            samples: 3,
+            threshold: 0.1,
+        });
+        let mut reports = vec![];
+        let adaptive = renderer()
+            .with_sampler(sampler)
+            .render_with(&c, &w, &cancel, |p| reports.push(*p));
+        assert!(!adaptive.cancelled);
+        let mut refined = 0;
+        for y in 0..23 {
+            for x in 0..37 {
+                if sampler.needs_refinement(&center.canvas, x, y) {
+                    refined += 1;
+                } else {
+                    assert_eq!(adaptive.canvas.at(x, y), center.canvas.at(x, y));
+                }
+            }
+        }
+        assert!(refined > 0 && refined < 37 * 23);
+        assert!(adaptive.progress.rays < 9 * center.progress.rays);
+        // both passes count towards the progress
+        let last = reports.last().unwrap();
+        assert_eq!(last.pixels_done, 2 * 37 * 23);
+        assert_eq!(last.fraction(), 1.0);
+        assert!(reports.iter().all(|p| p.pixels_total == 2 * 37 * 23));
+    }
+
+    #[test]
+    fn cancelling_an_adaptive_render_while_refining_keeps_the_first_pass() {
+        let (c, w) = test_scene();
+        let sampler = sampler().with_supersampling(Supersampling::Adaptive {
+            samples: 3,
+            threshold: 0.1,
+        });
+        let adaptive = renderer()
+            .with_threads(1)
+            .with_tile_size(4)
+            .with_sampler(sampler);
+        let never = CancelToken::new();
+        let center = renderer().render_with(&c, &w, &never, |_| {});
+        let full = adaptive.render_with(&c, &w, &never, |_| {});
+        let cancel = CancelToken::new();
+        // the first pass covers the first 37 * 23 pixels of progress
+        let rendered = adaptive.render_with(&c, &w, &cancel, |p| {
+            if p.pixels_done > 37 * 23 + 16 {
+                cancel.cancel();
+            }
+        });
+        assert!(rendered.cancelled);
+        let black = color(0, 0, 0);
+        for y in 0..23 {
+            for x in 0..37 {
+                let got = rendered.canvas.at(x, y);
+                assert!(got == center.canvas.at(x, y) || got == full.canvas.at(x, y));
+                if center.canvas.at(x, y) != black {
+                    assert_ne!(got, black, "at {x},{y}");
+                }
+            }
+        }
+        // the unrefined part is the first pass, not the finished render
+        assert_ne!(rendered.canvas, full.canvas);
+    }
+}
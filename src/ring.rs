@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn ring_pattern(a: impl IntoPattern, b: impl IntoPattern) -> RingPattern {
+    RingPattern::new(a, b)
+}
+
+/// Concentric rings around the y axis, alternating between `a` and `b`
+/// every unit of distance in x and z.
+#[derive(Clone, Debug)]
+pub struct RingPattern {
+    props: PatternProps,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl RingPattern {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for RingPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let distance = point.x().hypot(point.z());
+        if (distance.floor() as i64).rem_euclid(2) == 0 {
+            self.a.color_at(point)
+        } else {
+            self.b.color_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_ring_should_extend_in_both_x_and_z() {
+        let white = color(1, 1, 1);
+        let black = color(0, 0, 0);
+        let pattern = ring_pattern(white, black);
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white);
+        assert_eq!(pattern.color_at(point(1, 0, 0)), black);
+        assert_eq!(pattern.color_at(point(0, 0, 1)), black);
+        // 0.708 = just slightly more than √2/2
+        assert_eq!(pattern.color_at(point(0.708, 0, 0.708)), black);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::noise::splitmix64;
+
+use super::prelude::*;
+
+pub fn sampler() -> Sampler {
+    Sampler::default()
+}
+
+/// Where the rays for a pixel are fired.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Supersampling {
+    /// A single ray through the center of the pixel.
+    #[default]
+    Center,
+    /// An n by n grid of evenly spaced rays.
+    Grid(usize),
+    /// An n by n grid of cells with one ray at a random spot in each, which
+    /// trades the regular grid's aliasing for noise.
+    Jittered(usize),
+    /// One ray through the center of every pixel, and then `Jittered`
+    /// sampling only for pixels whose color differs from one of their
+    /// neighbors by more than `threshold` in any channel.
+    Adaptive { samples: usize, threshold: Num },
+}
+
+/// How the samples for a pixel are weighted by their distance from its
+/// center, measured in pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Every sample in the pixel counts equally.
+    #[default]
+    Box,
+    /// Falls off linearly, reaching zero one pixel away.
+    Tent,
+    /// A truncated Gaussian, which is smooth but a little blurry.
+    Gaussian,
+    /// The Mitchell-Netravali cubic with B = C = 1/3, whose negative lobes
+    /// keep edges sharper than the Gaussian does.
+    Mitchell,
+}
+
+impl Filter {
+    /// How far from the center of a pixel samples are taken.
+    pub fn radius(&self) -> Num {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    /// The weight of a sample at an offset from the center of the pixel.
+    pub fn weight(&self, dx: Num, dy: Num) -> Num {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: Num) -> Num {
+        let d = d.abs();
+        if d > self.radius() {
+            return 0.0;
+        }
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - d,
+            Filter::Gaussian => {
+                const ALPHA: Num = 2.0;
+                let r = self.radius();
+                (-ALPHA * d * d).exp() - (-ALPHA * r * r).exp()
+            }
+            Filter::Mitchell => {
+                const B: Num = 1.0 / 3.0;
+                const C: Num = 1.0 / 3.0;
+                let (d2, d3) = (d * d, d * d * d);
+                let w = if d < 1.0 {
+                    (12.0 - 9.0 * B - 6.0 * C) * d3
+                        + (-18.0 + 12.0 * B + 6.0 * C) * d2
+                        + (6.0 - 2.0 * B)
+                } else {
+                    (-B - 6.0 * C) * d3
+                        + (6.0 * B + 30.0 * C) * d2
+                        + (-12.0 * B - 48.0 * C) * d
+                        + (8.0 * B + 24.0 * C)
+                };
+                w / 6.0
+            }
+        }
+    }
+}
+
+/// Decides which rays are fired for each pixel and how their colors are
+/// combined. Samples are spread over the whole footprint of the filter, so
+/// wider filters blend in some of the neighboring pixels.
+///
+/// Jittered samples come from a generator seeded by the pixel and the
+/// sampler's seed, so a render looks the same no matter how it is split
+/// across threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sampler {
+    supersampling: Supersampling,
+    filter: Filter,
+    seed: u64,
+}
+
+impl Sampler {
+    pub fn with_supersampling(mut self, supersampling: Supersampling) -> Self {
+        self.supersampling = supersampling;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn supersampling(&self) -> Supersampling {
+        self.supersampling
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self.supersampling, Supersampling::Adaptive { .. })
+    }
+
+    /// The offsets from the center of a pixel, in pixels, at which it is
+    /// sampled. Adaptive sampling returns the offsets used for pixels that
+    /// need refining.
+    pub fn offsets(&self, x: usize, y: usize) -> Vec<(Num, Num)> {
+        match self.supersampling {
+            Supersampling::Center => vec![(0.0, 0.0)],
+            Supersampling::Grid(n) => self.strata(n, |_| (0.5, 0.5)),
+            Supersampling::Jittered(n) | Supersampling::Adaptive { samples: n, .. } => {
+                let mut state = self.seed ^ ((x as u64) << 32 | y as u64);
+                let mut random = || (splitmix64(&mut state) >> 11) as Num / (1u64 << 53) as Num;
+                self.strata(n, |_| (random(), random()))
+            }
+        }
+    }
+
+    /// Splits the filter's footprint into n by n cells and picks a spot in
+    /// each one, as fractions of the cell.
+    fn strata(&self, n: usize, mut spot: impl FnMut(usize) -> (Num, Num)) -> Vec<(Num, Num)> {
+        let n = n.max(1);
+        let r = self.filter.radius();
+        let cell = 2.0 * r / n as Num;
+        (0..n * n)
+            .map(|i| {
+                let (sx, sy) = spot(i);
+                let cx = (i % n) as Num + sx;
+                let cy = (i / n) as Num + sy;
+                (cx * cell - r, cy * cell - r)
+            })
+            .collect()
+    }
+
+    /// The filtered color of a pixel, where `shade` traces a ray through
+    /// the given offset from the center of the pixel.
+    pub fn sample(&self, x: usize, y: usize, shade: impl Fn(Num, Num) -> Color) -> Color {
+        let offsets = self.offsets(x, y);
+        if let [(dx, dy)] = offsets[..] {
+            return shade(dx, dy);
+        }
+        let mut sum = color(0, 0, 0);
+        let mut total = 0.0;
+        for (dx, dy) in offsets {
+            let weight = self.filter.weight(dx, dy);
+            if weight != 0.0 {
+                sum = sum + shade(dx, dy).mul_scalar(weight);
+                total += weight;
+            }
+        }
+        if total == 0.0 {
+            shade(0.0, 0.0)
+        } else {
+            sum.mul_scalar(1.0 / total)
+        }
+    }
+
+    /// Whether adaptive sampling should refine a pixel, given an image with
+    /// one sample through the center of every pixel.
+    pub fn needs_refinement(&self, image: &Canvas, x: usize, y: usize) -> bool {
+        let Supersampling::Adaptive { threshold, .. } = self.supersampling else {
+            return false;
+        };
+        let c = image.at(x, y);
+        let neighbors = [
+            (x.checked_sub(1), Some(y)),
+            (Some(x + 1).filter(|&x| x < image.width), Some(y)),
+            (Some(x), y.checked_sub(1)),
+            (Some(x), Some(y + 1).filter(|&y| y < image.height)),
+        ];
+        neighbors.into_iter().any(|n| match n {
+            (Some(nx), Some(ny)) => {
+                let d = image.at(nx, ny) - c;
+                d.r().abs().max(d.g().abs()).max(d.b().abs()) > threshold
+            }
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_sampler_fires_one_ray_through_the_center() {
+        let s = sampler();
+        assert_eq!(s.offsets(3, 4), vec![(0.0, 0.0)]);
+        assert_eq!(s.sample(3, 4, |dx, dy| color(dx, dy, 1)), color(0, 0, 1));
+    }
+
+    #[test]
+    fn grid_sampling_spreads_rays_evenly_over_the_pixel() {
+        let s = sampler().with_supersampling(Supersampling::Grid(2));
+        assert_eq!(
+            s.offsets(0, 0),
+            vec![(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]
+        );
+    }
+
+    #[test]
+    fn grid_sampling_covers_the_filter_footprint() {
+        let s = sampler()
+            .with_supersampling(Supersampling::Grid(2))
+            .with_filter(Filter::Tent);
+        assert_eq!(
+            s.offsets(0, 0),
+            vec![(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)]
+        );
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let s = sampler().with_supersampling(Supersampling::Jittered(4));
+        let offsets = s.offsets(7, 2);
+        assert_eq!(offsets.len(), 16);
+        for (i, (dx, dy)) in offsets.into_iter().enumerate() {
+            let (cx, cy) = ((i % 4) as Num * 0.25 - 0.5, (i / 4) as Num * 0.25 - 0.5);
+            assert!((cx..cx + 0.25).contains(&dx), "{dx} not in cell {i}");
+            assert!((cy..cy + 0.25).contains(&dy), "{dy} not in cell {i}");
+        }
+    }
+
+    #[test]
+    fn jittered_samples_are_repeatable_per_pixel() {
+        let s = sampler().with_supersampling(Supersampling::Jittered(3));
+        assert_eq!(s.offsets(5, 6), s.offsets(5, 6));
+        assert_ne!(s.offsets(5, 6), s.offsets(6, 5));
+        assert_ne!(s.offsets(5, 6), s.with_seed(1).offsets(5, 6));
+    }
+
+    #[test]
+    fn filter_weights() {
+        assert_eq!(Filter::Box.weight(0.4, -0.4), 1.0);
+        assert_eq!(Filter::Box.weight(0.6, 0.0), 0.0);
+        assert_eq!(Filter::Tent.weight(0.5, 0.0), 0.5);
+        assert_eq!(Filter::Tent.weight(0.5, 0.5), 0.25);
+        assert_eq!(Filter::Gaussian.weight(1.5, 0.0), 0.0);
+        assert!(Filter::Gaussian.weight(0.0, 0.0) > Filter::Gaussian.weight(0.5, 0.0));
+        assert!(nums_equal(Filter::Mitchell.weight(0.0, 0.0), 64.0 / 81.0));
+        assert!(nums_equal(
+            Filter::Mitchell.weight(1.0, 0.0),
+            1.0 / 18.0 * 8.0 / 9.0
+        ));
+        assert!(Filter::Mitchell.weight(1.5, 0.0) < 0.0);
+        assert!(nums_equal(Filter::Mitchell.weight(2.0, 0.0), 0.0));
+    }
+
+    #[test]
+    fn sampling_a_constant_color_gives_that_color() {
+        for filter in [
+            Filter::Box,
+            Filter::Tent,
+            Filter::Gaussian,
+            Filter::Mitchell,
+        ] {
+            let s = sampler()
+                .with_supersampling(Supersampling::Jittered(4))
+                .with_filter(filter);
+            assert_eq!(
+                s.sample(1, 1, |_, _| color(0.2, 0.4, 0.6)),
+                color(0.2, 0.4, 0.6)
+            );
+        }
+    }
+
+    #[test]
+    fn sampling_an_edge_blends_both_sides() {
+        let s = sampler().with_supersampling(Supersampling::Grid(4));
+        let edge = |dx: Num, _| {
+            if dx < 0.0 {
+                color(1, 1, 1)
+            } else {
+                color(0, 0, 0)
+            }
+        };
+        assert_eq!(s.sample(0, 0, edge), color(0.5, 0.5, 0.5));
+        // the tent filter weighs the samples nearest the center most
+        let s = s.with_filter(Filter::Tent);
+        let quarter = |dx: Num, _| {
+            if dx < -0.5 {
+                color(1, 1, 1)
+            } else {
+                color(0, 0, 0)
+            }
+        };
+        assert!(s.sample(0, 0, quarter).r() < 0.25);
+    }
+
+    #[test]
+    fn adaptive_sampling_refines_pixels_next_to_a_change() {
+        let s = sampler().with_supersampling(Supersampling::Adaptive {
+            samples: 4,
+            threshold: 0.1,
+        });
+        let mut image = canvas(4, 1);
+        image.write(2, 0, color(0.5, 0, 0));
+        image.write(3, 0, color(0.55, 0, 0));
+        assert!(!s.needs_refinement(&image, 0, 0));
+        assert!(s.needs_refinement(&image, 1, 0));
+        assert!(s.needs_refinement(&image, 2, 0));
+        assert!(!s.needs_refinement(&image, 3, 0));
+        assert_eq!(s.offsets(0, 0).len(), 16);
+    }
+}
@@ -0,0 +1,420 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::prelude::*;
+use crate::stats;
+
+/// The state every shape carries regardless of its geometry. The inverse
+/// transforms are cached since they are needed for every ray and normal.
+///
+/// Shapes nested in groups also cache the combined transform of all of
+/// their ancestors (`parent`), which is what converting between world and
+/// object space needs to walk the parent chain.
+#[derive(Clone, Debug)]
+pub struct ShapeProps {
+    transform: Matrix,
+    inverse: Matrix,
+    parent: Matrix,
+    world_inverse: Matrix,
+    world_inverse_transpose: Matrix,
+    material: Material,
+    normal_perturbation: Option<Arc<dyn NormalPerturbation>>,
+}
+
+impl Default for ShapeProps {
+    fn default() -> Self {
+        Self {
+            transform: identity(),
+            inverse: identity(),
+            parent: identity(),
+            world_inverse: identity(),
+            world_inverse_transpose: identity(),
+            material: material(),
+            normal_perturbation: None,
+        }
+    }
+}
+
+/// Perturbations can't be compared by value, so props only have equal
+/// perturbations when they share the very same one.
+impl PartialEq for ShapeProps {
+    fn eq(&self, other: &Self) -> bool {
+        let same_perturbation = match (&self.normal_perturbation, &other.normal_perturbation) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_perturbation
+            && self.transform == other.transform
+            && self.parent == other.parent
+            && self.material == other.material
+    }
+}
+
+impl ShapeProps {
+    pub fn transform(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    /// The inverse of `world_transform`, which takes world space points
+    /// into object space.
+    pub fn world_inverse(&self) -> Matrix {
+        self.world_inverse
+    }
+
+    /// Takes object space normals out to world space.
+    pub fn world_inverse_transpose(&self) -> Matrix {
+        self.world_inverse_transpose
+    }
+
+    /// The combined transform of every group this shape is nested in.
+    pub fn parent_transform(&self) -> Matrix {
+        self.parent
+    }
+
+    /// The transform from object space all the way out to world space.
+    pub fn world_transform(&self) -> Matrix {
+        self.parent.mul_matrix(self.transform)
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn set_transform(&mut self, tf: Matrix) {
+        self.transform = tf;
+        self.inverse = tf.inverse();
+        self.update_world_inverse();
+    }
+
+    pub fn set_parent_transform(&mut self, parent: Matrix) {
+        self.parent = parent;
+        self.update_world_inverse();
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn normal_perturbation(&self) -> Option<&Arc<dyn NormalPerturbation>> {
+        self.normal_perturbation.as_ref()
+    }
+
+    pub fn set_normal_perturbation(&mut self, perturbation: Option<Arc<dyn NormalPerturbation>>) {
+        self.normal_perturbation = perturbation;
+    }
+
+    fn update_world_inverse(&mut self) {
+        self.world_inverse = self.world_transform().inverse();
+        self.world_inverse_transpose = self.world_inverse.transpose();
+    }
+}
+
+/// A primitive that can be placed in a world. Implementors only deal with
+/// object space: rays and points are converted from world space before
+/// `local_intersect` and `local_normal_at` are called.
+pub trait Shape: fmt::Debug + Send + Sync {
+    fn props(&self) -> &ShapeProps;
+
+    fn props_mut(&mut self) -> &mut ShapeProps;
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_>;
+
+    fn local_normal_at(&self, point: Point) -> Vector;
+
+    /// The box that contains the shape in object space.
+    fn local_bounds(&self) -> Bounds;
+
+    /// The box that contains the shape once its transform is applied.
+    fn parent_space_bounds(&self) -> Bounds {
+        self.local_bounds().transform(self.transform())
+    }
+
+    fn transform(&self) -> Matrix {
+        self.props().transform()
+    }
+
+    fn set_transform(&mut self, tf: Matrix) {
+        self.props_mut().set_transform(tf);
+        self.propagate_transform();
+    }
+
+    /// Called by a group when this shape is added to it, or when the
+    /// transform of one of its ancestors changes.
+    fn set_parent_transform(&mut self, parent: Matrix) {
+        self.props_mut().set_parent_transform(parent);
+        self.propagate_transform();
+    }
+
+    /// Pushes this shape's world transform down to its children, if any.
+    fn propagate_transform(&mut self) {}
+
+    fn children(&self) -> &[Box<dyn Shape>] {
+        &[]
+    }
+
+    /// Reports whether `other` is this shape or one of its descendants.
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self as *const Self, other as *const dyn Shape)
+            || self.children().iter().any(|child| child.includes(other))
+    }
+
+    /// The number of primitives (shapes without children) in this shape.
+    fn primitive_count(&self) -> usize {
+        if self.children().is_empty() {
+            1
+        } else {
+            self.children().iter().map(|c| c.primitive_count()).sum()
+        }
+    }
+
+    /// Reorganizes any children into a bounding volume hierarchy so that
+    /// no group directly holds more than `threshold` of them.
+    fn divide(&mut self, threshold: usize) {}
+
+    fn with_transform(mut self, tf: Matrix) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_transform(tf);
+        self
+    }
+
+    fn material(&self) -> &Material {
+        self.props().material()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.props_mut().set_material(material);
+    }
+
+    fn with_material(mut self, material: Material) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_material(material);
+        self
+    }
+
+    /// Adds surface detail, like bumps or a normal map, by bending the
+    /// normals of this shape. Only primitives use it: a perturbation on a
+    /// group or CSG shape is not passed on to its children.
+    fn set_normal_perturbation(&mut self, perturbation: Arc<dyn NormalPerturbation>) {
+        self.props_mut().set_normal_perturbation(Some(perturbation));
+    }
+
+    fn with_normal_perturbation(mut self, perturbation: impl NormalPerturbation + 'static) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_normal_perturbation(Arc::new(perturbation));
+        self
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersections<'_> {
+        let ray = ray.transform(self.props().inverse);
+        if self.children().is_empty() {
+            stats::record_primitive_test();
+        }
+        self.local_intersect(ray)
+    }
+
+    /// Like `local_normal_at`, but with access to the intersection being
+    /// shaded for shapes whose normal varies across a face.
+    fn local_normal_at_hit(&self, point: Point, hit: &Intersection) -> Vector {
+        self.local_normal_at(point)
+    }
+
+    fn world_to_object(&self, point: Point) -> Point {
+        self.props().world_inverse.mul_point(point)
+    }
+
+    fn normal_to_world(&self, normal: Vector) -> Vector {
+        let mut normal = self.props().world_inverse_transpose.mul_vector(normal);
+        normal.set_w(0.0);
+        normal.normalize()
+    }
+
+    fn normal_at(&self, world_point: Point) -> Vector {
+        let local_point = self.world_to_object(world_point);
+        let local_normal = self.local_normal_at(local_point);
+        self.perturb_normal(world_point, self.normal_to_world(local_normal))
+    }
+
+    fn normal_at_hit(&self, world_point: Point, hit: &Intersection) -> Vector {
+        let local_point = self.world_to_object(world_point);
+        let local_normal = self.local_normal_at_hit(local_point, hit);
+        self.perturb_normal(world_point, self.normal_to_world(local_normal))
+    }
+
+    /// Applies the shape's normal perturbation, if any, to a world space
+    /// normal.
+    fn perturb_normal(&self, world_point: Point, normal: Vector) -> Vector {
+        match self.props().normal_perturbation() {
+            Some(perturbation) => perturbation.perturb(self.props(), world_point, normal),
+            None => normal,
+        }
+    }
+}
+
+/// Shapes are compared by identity, which is what intersections need to
+/// know whether they refer to the same object.
+impl PartialEq for dyn Shape + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct TestShape {
+        props: ShapeProps,
+        pub(crate) saved_ray: Mutex<Option<Ray>>,
+    }
+
+    pub(crate) fn test_shape() -> TestShape {
+        TestShape::default()
+    }
+
+    impl Shape for TestShape {
+        fn props(&self) -> &ShapeProps {
+            &self.props
+        }
+
+        fn props_mut(&mut self) -> &mut ShapeProps {
+            &mut self.props
+        }
+
+        fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+            *self.saved_ray.lock().unwrap() = Some(ray);
+            Intersections::default()
+        }
+
+        fn local_normal_at(&self, point: Point) -> Vector {
+            vector(point.x(), point.y(), point.z())
+        }
+
+        fn local_bounds(&self) -> Bounds {
+            bounds(point(-1, -1, -1), point(1, 1, 1))
+        }
+    }
+
+    #[test]
+    fn the_default_transformation() {
+        let s = test_shape();
+        assert_eq!(s.transform(), identity());
+    }
+
+    #[test]
+    fn assigning_a_transformation() {
+        let mut s = test_shape();
+        s.set_transform(translation(2, 3, 4));
+        assert_eq!(s.transform(), translation(2, 3, 4));
+    }
+
+    #[test]
+    fn the_default_material() {
+        let s = test_shape();
+        assert_eq!(s.material(), &material());
+    }
+
+    #[test]
+    fn assigning_a_material() {
+        let mut s = test_shape();
+        let m = material().with_ambient(1);
+        s.set_material(m.clone());
+        assert_eq!(s.material(), &m);
+    }
+
+    #[test]
+    fn intersecting_a_scaled_shape_with_a_ray() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let s = test_shape().with_transform(scaling(2, 2, 2));
+        let xs = s.intersect(r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved.origin(), point(0, 0, -2.5));
+        assert_eq!(saved.dir(), vector(0, 0, 0.5));
+    }
+
+    #[test]
+    fn intersecting_a_translated_shape_with_a_ray() {
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let s = test_shape().with_transform(translation(5, 0, 0));
+        let xs = s.intersect(r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved.origin(), point(-5, 0, -5));
+        assert_eq!(saved.dir(), vector(0, 0, 1));
+    }
+
+    #[test]
+    fn computing_the_normal_on_a_translated_shape() {
+        let s = test_shape().with_transform(translation(0, 1, 0));
+        let n = s.normal_at(point(0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, vector(0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn computing_the_normal_on_a_transformed_shape() {
+        let m = scaling(1, 0.5, 1).mul_matrix(rotation_z(PI / 5.0));
+        let s = test_shape().with_transform(m);
+        let n = s.normal_at(point(0.0, SQRT_2 / 2.0, -SQRT_2 / 2.0));
+        assert_eq!(n, vector(0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn a_shape_has_no_parent_transform_by_default() {
+        let s = test_shape();
+        assert_eq!(s.props().parent_transform(), identity());
+        assert!(s.children().is_empty());
+    }
+
+    #[test]
+    fn querying_a_shapes_bounding_box_in_its_parents_space() {
+        let s = sphere().with_transform(translation(1, -3, 5).mul_matrix(scaling(0.5, 2, 4)));
+        let b = s.parent_space_bounds();
+        assert_eq!(b.min(), point(0.5, -5, 1));
+        assert_eq!(b.max(), point(1.5, -1, 9));
+    }
+
+    #[test]
+    fn intersecting_a_primitive_is_counted() {
+        take_stats();
+        let s = test_shape();
+        s.intersect(ray(point(0, 0, -5), vector(0, 0, 1)));
+        assert_eq!(take_stats().primitive_tests, 1);
+    }
+
+    #[test]
+    fn shapes_are_compared_by_identity() {
+        let s1 = test_shape();
+        let s2 = test_shape();
+        let a: &dyn Shape = &s1;
+        let b: &dyn Shape = &s2;
+        assert!(a == a);
+        assert!(a != b);
+    }
+
+    #[test]
+    fn a_normal_perturbation_is_applied_to_world_normals() {
+        #[derive(Debug)]
+        struct Flip;
+        impl NormalPerturbation for Flip {
+            fn perturb(&self, props: &ShapeProps, world_point: Point, normal: Vector) -> Vector {
+                -normal
+            }
+        }
+        let s = test_shape().with_normal_perturbation(Flip);
+        assert!(s.props().normal_perturbation().is_some());
+        let n = s.normal_at(point(0, 0, 1));
+        assert_eq!(n, vector(0, 0, -1));
+    }
+}
@@ -0,0 +1,157 @@
+use super::prelude::*;
+use crate::triangle::moller_trumbore;
+
+pub fn smooth_triangle(
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+) -> SmoothTriangle {
+    SmoothTriangle::new(p1, p2, p3, n1, n2, n3)
+}
+
+/// A triangle with a normal at each vertex. The normal at a hit is
+/// interpolated from the vertex normals using the hit's u and v, which makes
+/// meshes look smooth without adding more faces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmoothTriangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+    e1: Vector,
+    e2: Vector,
+    props: ShapeProps,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        Self {
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+            props: ShapeProps::default(),
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+
+    pub fn n1(&self) -> Vector {
+        self.n1
+    }
+
+    pub fn n2(&self) -> Vector {
+        self.n2
+    }
+
+    pub fn n3(&self) -> Vector {
+        self.n3
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => intersections([intersection(t, self).with_uv(u, v)]),
+            None => Intersections::default(),
+        }
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::empty()
+            .with_point(self.p1)
+            .with_point(self.p2)
+            .with_point(self.p3)
+    }
+
+    /// Without a hit to interpolate with there is only the face normal.
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        self.e2.cross(self.e1).normalize()
+    }
+
+    fn local_normal_at_hit(&self, _point: Point, hit: &Intersection) -> Vector {
+        self.n2.mul_scalar(hit.u())
+            + self.n3.mul_scalar(hit.v())
+            + self.n1.mul_scalar(1.0 - hit.u() - hit.v())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_smooth_triangle() -> SmoothTriangle {
+        smooth_triangle(
+            point(0, 1, 0),
+            point(-1, 0, 0),
+            point(1, 0, 0),
+            vector(0, 1, 0),
+            vector(-1, 0, 0),
+            vector(1, 0, 0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_smooth_triangle() {
+        let tri = test_smooth_triangle();
+        assert_eq!(tri.p1(), point(0, 1, 0));
+        assert_eq!(tri.p2(), point(-1, 0, 0));
+        assert_eq!(tri.p3(), point(1, 0, 0));
+        assert_eq!(tri.n1(), vector(0, 1, 0));
+        assert_eq!(tri.n2(), vector(-1, 0, 0));
+        assert_eq!(tri.n3(), vector(1, 0, 0));
+    }
+
+    #[test]
+    fn an_intersection_with_a_smooth_triangle_stores_u_v() {
+        let tri = test_smooth_triangle();
+        let r = ray(point(-0.2, 0.3, -2), vector(0, 0, 1));
+        let xs = tri.local_intersect(r);
+        assert!(nums_equal(xs[0].u(), 0.45));
+        assert!(nums_equal(xs[0].v(), 0.25));
+    }
+
+    #[test]
+    fn a_smooth_triangle_uses_u_v_to_interpolate_the_normal() {
+        let tri = test_smooth_triangle();
+        let i = intersection(1, &tri).with_uv(0.45, 0.25);
+        let n = tri.normal_at_hit(point(0, 0, 0), &i);
+        assert_eq!(n, vector(-0.5547, 0.83205, 0));
+    }
+
+    #[test]
+    fn preparing_the_normal_on_a_smooth_triangle() {
+        let tri = test_smooth_triangle();
+        let i = intersection(1, &tri).with_uv(0.45, 0.25);
+        let r = ray(point(-0.2, 0.3, -2), vector(0, 0, 1));
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(comps.normalv, vector(-0.5547, 0.83205, 0));
+    }
+}
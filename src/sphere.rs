@@ -6,11 +6,16 @@ pub fn sphere() -> Sphere {
     Sphere::new(point(0, 0, 0), 1)
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// A unit sphere made of glass.
+pub fn glass_sphere() -> Sphere {
+    sphere().with_material(material().with_transparency(1).with_refractive_index(1.5))
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Sphere {
     origin: Point,
     radius: Num,
-    tf: Matrix,
+    props: ShapeProps,
 }
 
 impl Sphere {
@@ -18,40 +23,41 @@ impl Sphere {
         Self {
             origin,
             radius: radius.into(),
-            tf: identity(),
+            props: ShapeProps::default(),
         }
     }
+}
 
-    pub fn with_transform(mut self, tf: Matrix) -> Self {
-        self.tf = tf;
-        self
+impl Shape for Sphere {
+    fn props(&self) -> &ShapeProps {
+        &self.props
     }
 
-    pub fn set_transform(&mut self, tf: Matrix) {
-        self.tf = tf;
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
     }
 
-    pub fn intersect(&self, ray: Ray) -> Intersections {
-        let ray = ray.transform(self.tf.inverse());
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
         let sphere_to_ray: Vector = ray.origin().sub(self.origin);
         let a: Num = ray.dir().dot(ray.dir());
         let b = 2.0 * ray.dir().dot(sphere_to_ray);
-        let c = sphere_to_ray.dot(sphere_to_ray) - 1.0;
+        let c = sphere_to_ray.dot(sphere_to_ray) - self.radius * self.radius;
         let disc: Num = (b * b) - (4.0 * a * c);
         if disc < 0.0 {
             return Intersections::default();
         }
         let t1 = (-b - f64::sqrt(disc)) / (2.0 * a);
         let t2 = (-b + f64::sqrt(disc)) / (2.0 * a);
-        intersections([intersection(t1, *self), intersection(t2, *self)])
+        intersections([intersection(t1, self), intersection(t2, self)])
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let r = vector(self.radius, self.radius, self.radius);
+        bounds(self.origin.sub_vector(r), self.origin.add_vector(r))
     }
 
-    pub fn normal_at(&self, world_point: Point) -> Vector {
-        let obj_point = self.tf.inverse().mul_point(world_point);
-        let obj_normal = obj_point - point(0, 0, 0);
-        let mut world_normal = self.tf.inverse().transpose().mul_vector(obj_normal);
-        world_normal.set_w(0.0);
-        world_normal.normalize()
+    fn local_normal_at(&self, obj_point: Point) -> Vector {
+        obj_point - self.origin
     }
 }
 
@@ -67,7 +73,7 @@ mod tests {
         let xs = s.intersect(r);
         assert_eq!(
             xs,
-            intersections([intersection(4.0, s), intersection(6.0, s),])
+            intersections([intersection(4.0, &s), intersection(6.0, &s),])
         );
     }
 
@@ -78,7 +84,7 @@ mod tests {
         let xs = s.intersect(r);
         assert_eq!(
             xs,
-            intersections([intersection(5.0, s), intersection(5.0, s),])
+            intersections([intersection(5.0, &s), intersection(5.0, &s),])
         );
     }
 
@@ -97,7 +103,7 @@ mod tests {
         let xs = s.intersect(r);
         assert_eq!(
             xs,
-            intersections([intersection(-1.0, s), intersection(1.0, s),])
+            intersections([intersection(-1.0, &s), intersection(1.0, &s),])
         );
     }
 
@@ -108,14 +114,14 @@ mod tests {
         let xs = s.intersect(r);
         assert_eq!(
             xs,
-            intersections([intersection(-6.0, s), intersection(-4.0, s),])
+            intersections([intersection(-6.0, &s), intersection(-4.0, &s),])
         );
     }
 
     #[test]
     fn sphere_default_transformation() {
         let s = sphere();
-        assert_eq!(s.tf, identity());
+        assert_eq!(s.transform(), identity());
     }
 
     #[test]
@@ -123,7 +129,7 @@ mod tests {
         let mut s = sphere();
         let t = translation(2, 3, 4);
         s.set_transform(t);
-        assert_eq!(s.tf, t);
+        assert_eq!(s.transform(), t);
     }
 
     #[test]
@@ -132,7 +138,7 @@ mod tests {
         let mut s = sphere();
         s.set_transform(scaling(2, 2, 2));
         let xs = s.intersect(r);
-        assert_eq!(xs, intersections([intersection(3, s), intersection(7, s),]));
+        assert_eq!(xs, intersections([intersection(3, &s), intersection(7, &s),]));
     }
 
     #[test]
@@ -210,6 +216,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_normal_on_translated_sphere() {
         let s = sphere().with_transform(translation(0, 1, 0));
         let n = s.normal_at(point(0, 1.70711, -0.70711));
@@ -225,4 +232,33 @@ mod tests {
         let n = s.normal_at(point(0.0, SQRT_2 / 2.0, -SQRT_2 / 2.0));
         assert_eq!(n, vector(0, 0.97014, -0.24254));
     }
+
+    #[test]
+    fn a_sphere_has_a_default_material() {
+        let s = sphere();
+        assert_eq!(s.material(), &material());
+    }
+
+    #[test]
+    fn a_sphere_may_be_assigned_a_material() {
+        let mut s = sphere();
+        let m = material().with_ambient(1);
+        s.set_material(m.clone());
+        assert_eq!(s.material(), &m);
+    }
+
+    #[test]
+    fn a_sphere_has_a_bounding_box() {
+        let b = sphere().local_bounds();
+        assert_eq!(b.min(), point(-1, -1, -1));
+        assert_eq!(b.max(), point(1, 1, 1));
+    }
+
+    #[test]
+    fn a_helper_for_producing_a_sphere_with_a_glassy_material() {
+        let s = glass_sphere();
+        assert_eq!(s.transform(), identity());
+        assert_eq!(s.material().transparency, 1.0);
+        assert_eq!(s.material().refractive_index, 1.5);
+    }
 }
@@ -0,0 +1,93 @@
+//! Counters for how much work intersecting rays with a scene takes, and how
+//! much of it was avoided by bounding boxes. Counters are kept per thread so
+//! that they are cheap to update and don't interfere across threads.
+
+use std::{cell::Cell, fmt, ops};
+
+thread_local! {
+    static STATS: Cell<Stats> = Cell::new(Stats::default());
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Rays traced through a world, including shadow rays and the rays
+    /// that reflection and refraction spawn.
+    pub rays: u64,
+    /// Rays tested against a bounding box.
+    pub bounds_tests: u64,
+    /// Bounding box tests that missed, skipping everything inside the box.
+    pub bounds_misses: u64,
+    /// Rays tested against a primitive (a shape with no children).
+    pub primitive_tests: u64,
+    /// Primitive tests avoided because their bounding box was missed.
+    pub primitive_tests_saved: u64,
+}
+
+impl ops::Add for Stats {
+    type Output = Stats;
+    fn add(self, rhs: Self) -> Self::Output {
+        Stats {
+            rays: self.rays + rhs.rays,
+            bounds_tests: self.bounds_tests + rhs.bounds_tests,
+            bounds_misses: self.bounds_misses + rhs.bounds_misses,
+            primitive_tests: self.primitive_tests + rhs.primitive_tests,
+            primitive_tests_saved: self.primitive_tests_saved + rhs.primitive_tests_saved,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rays: {}, bounds tests: {} ({} missed), primitive tests: {} ({} saved)",
+            self.rays,
+            self.bounds_tests,
+            self.bounds_misses,
+            self.primitive_tests,
+            self.primitive_tests_saved
+        )
+    }
+}
+
+/// The counters accumulated on the current thread.
+pub fn stats() -> Stats {
+    STATS.get()
+}
+
+/// Returns the counters accumulated on the current thread and resets them.
+pub fn take_stats() -> Stats {
+    STATS.replace(Stats::default())
+}
+
+/// Adds counters gathered on another thread, like a render worker, to the
+/// current thread's.
+pub(crate) fn merge_stats(other: Stats) {
+    update(|s| *s = *s + other);
+}
+
+pub(crate) fn record_bounds_test(hit: bool, primitives_inside: usize) {
+    update(|s| {
+        s.bounds_tests += 1;
+        if !hit {
+            s.bounds_misses += 1;
+            s.primitive_tests_saved += primitives_inside as u64;
+        }
+    });
+}
+
+pub(crate) fn record_ray() {
+    update(|s| s.rays += 1);
+}
+
+pub(crate) fn record_primitive_test() {
+    update(|s| s.primitive_tests += 1);
+}
+
+fn update(f: impl FnOnce(&mut Stats)) {
+    STATS.with(|cell| {
+        let mut s = cell.get();
+        f(&mut s);
+        cell.set(s);
+    });
+}
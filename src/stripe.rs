@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn stripe_pattern(a: impl IntoPattern, b: impl IntoPattern) -> StripePattern {
+    StripePattern::new(a, b)
+}
+
+/// Alternates between `a` and `b` every unit along x.
+#[derive(Clone, Debug)]
+pub struct StripePattern {
+    props: PatternProps,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl StripePattern {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for StripePattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        if (point.x().floor() as i64).rem_euclid(2) == 0 {
+            self.a.color_at(point)
+        } else {
+            self.b.color_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        color(1, 1, 1)
+    }
+
+    fn black() -> Color {
+        color(0, 0, 0)
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_y() {
+        let pattern = stripe_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 1, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 2, 0)), white());
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_z() {
+        let pattern = stripe_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0, 0, 1)), white());
+        assert_eq!(pattern.color_at(point(0, 0, 2)), white());
+    }
+
+    #[test]
+    fn a_stripe_pattern_alternates_in_x() {
+        let pattern = stripe_pattern(white(), black());
+        assert_eq!(pattern.color_at(point(0, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0.9, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(1, 0, 0)), black());
+        assert_eq!(pattern.color_at(point(-0.1, 0, 0)), black());
+        assert_eq!(pattern.color_at(point(-1, 0, 0)), black());
+        assert_eq!(pattern.color_at(point(-1.1, 0, 0)), white());
+    }
+
+    #[test]
+    fn stripes_with_an_object_transformation() {
+        let object = sphere().with_transform(scaling(2, 2, 2));
+        let pattern = stripe_pattern(white(), black());
+        assert_eq!(pattern.color_at_shape(&object, point(1.5, 0, 0)), white());
+    }
+
+    #[test]
+    fn stripes_with_a_pattern_transformation() {
+        let object = sphere();
+        let pattern = stripe_pattern(white(), black()).with_transform(scaling(2, 2, 2));
+        assert_eq!(pattern.color_at_shape(&object, point(1.5, 0, 0)), white());
+    }
+
+    #[test]
+    fn stripes_with_both_an_object_and_a_pattern_transformation() {
+        let object = sphere().with_transform(scaling(2, 2, 2));
+        let pattern = stripe_pattern(white(), black()).with_transform(translation(0.5, 0, 0));
+        assert_eq!(pattern.color_at_shape(&object, point(2.5, 0, 0)), white());
+    }
+
+    #[test]
+    fn stripes_can_nest_other_patterns() {
+        let red = color(1, 0, 0);
+        let inner = stripe_pattern(white(), red).with_transform(scaling(0.5, 0.5, 0.5));
+        let pattern = stripe_pattern(inner, black());
+        assert_eq!(pattern.color_at(point(0.25, 0, 0)), white());
+        assert_eq!(pattern.color_at(point(0.75, 0, 0)), red);
+        assert_eq!(pattern.color_at(point(1.25, 0, 0)), black());
+    }
+}
@@ -0,0 +1,164 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn image_texture(canvas: Canvas) -> ImageTexture {
+    ImageTexture::new(canvas)
+}
+
+/// How an image texture picks a color for coordinates that fall between
+/// the centers of its pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// The color of the closest pixel.
+    #[default]
+    Nearest,
+    /// A weighted average of the four surrounding pixels.
+    Bilinear,
+}
+
+/// A two dimensional pattern backed by an image, with v = 1 at the top row
+/// of the canvas and v = 0 at the bottom.
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    canvas: Arc<Canvas>,
+    sampling: Sampling,
+}
+
+impl ImageTexture {
+    pub fn new(canvas: Canvas) -> Self {
+        Self {
+            canvas: Arc::new(canvas),
+            sampling: Sampling::default(),
+        }
+    }
+
+    pub fn from_ppm_file(path: impl AsRef<Path>) -> Result<Self, PpmError> {
+        Canvas::from_ppm_file(path).map(Self::new)
+    }
+
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+}
+
+impl UvPattern for ImageTexture {
+    fn uv_color_at(&self, u: Num, v: Num) -> Color {
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        if width == 0 || height == 0 {
+            return color(0, 0, 0);
+        }
+        // pixel coordinates, with y flipped so that v = 0 is the bottom row
+        let x = u.clamp(0.0, 1.0) * (width - 1) as Num;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * (height - 1) as Num;
+        match self.sampling {
+            Sampling::Nearest => self.canvas.at(x.round() as usize, y.round() as usize),
+            Sampling::Bilinear => {
+                let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+                let (fx, fy) = (x - x0 as Num, y - y0 as Num);
+                let lerp = |a: Color, b: Color, t: Num| a + (b - a).mul_scalar(t);
+                let top = lerp(self.canvas.at(x0, y0), self.canvas.at(x1, y0), fx);
+                let bottom = lerp(self.canvas.at(x0, y1), self.canvas.at(x1, y1), fx);
+                lerp(top, bottom, fy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_image() -> Canvas {
+        let mut ppm = String::from("P3\n10 10\n10\n");
+        for y in 0..10 {
+            for x in 0..10 {
+                let v = (x + y) % 10;
+                ppm.push_str(&format!("{v} {v} {v}  "));
+            }
+            ppm.push('\n');
+        }
+        Canvas::from_ppm(ppm.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn checker_pattern_in_2d_from_an_image() {
+        let pattern = image_texture(gradient_image());
+        let cases = [
+            (0.0, 0.0, 0.9),
+            (0.3, 0.0, 0.2),
+            (0.6, 0.3, 0.1),
+            (1.0, 1.0, 0.9),
+        ];
+        for (u, v, expected) in cases {
+            let c = pattern.uv_color_at(u, v);
+            assert_eq!(c, color(expected, expected, expected), "at {u},{v}");
+        }
+    }
+
+    #[test]
+    fn nearest_sampling_picks_the_closest_pixel() {
+        let mut image = canvas(2, 2);
+        image.write(0, 0, color(1, 0, 0));
+        image.write(1, 0, color(0, 1, 0));
+        image.write(0, 1, color(0, 0, 1));
+        image.write(1, 1, color(1, 1, 1));
+        let pattern = image_texture(image);
+        assert_eq!(pattern.uv_color_at(0.1, 0.9), color(1, 0, 0));
+        assert_eq!(pattern.uv_color_at(0.9, 0.9), color(0, 1, 0));
+        assert_eq!(pattern.uv_color_at(0.1, 0.1), color(0, 0, 1));
+        assert_eq!(pattern.uv_color_at(0.9, 0.1), color(1, 1, 1));
+    }
+
+    #[test]
+    fn bilinear_sampling_blends_the_surrounding_pixels() {
+        let mut image = canvas(2, 2);
+        image.write(0, 0, color(1, 0, 0));
+        image.write(1, 0, color(0, 1, 0));
+        image.write(0, 1, color(0, 0, 1));
+        image.write(1, 1, color(1, 1, 1));
+        let pattern = image_texture(image).with_sampling(Sampling::Bilinear);
+        assert_eq!(pattern.uv_color_at(0.0, 1.0), color(1, 0, 0));
+        assert_eq!(pattern.uv_color_at(1.0, 0.0), color(1, 1, 1));
+        assert_eq!(pattern.uv_color_at(0.5, 0.5), color(0.5, 0.5, 0.5));
+        assert_eq!(pattern.uv_color_at(0.5, 1.0), color(0.5, 0.5, 0));
+        assert_eq!(pattern.uv_color_at(0.25, 0.0), color(0.25, 0.25, 1));
+    }
+
+    #[test]
+    fn sampling_clamps_coordinates_outside_the_image() {
+        let pattern = image_texture(gradient_image()).with_sampling(Sampling::Bilinear);
+        assert_eq!(
+            pattern.uv_color_at(-0.5, 1.5),
+            pattern.uv_color_at(0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn an_image_texture_mapped_onto_a_sphere() {
+        let mut image = canvas(2, 1);
+        image.write(0, 0, color(1, 0, 0));
+        image.write(1, 0, color(0, 0, 1));
+        let pattern = texture_map_pattern(image_texture(image), UvMapping::Spherical);
+        // u is 0 at -z and 0.5 at +z
+        assert_eq!(pattern.color_at(point(0, 0, -1)), color(1, 0, 0));
+        assert_eq!(pattern.color_at(point(-1, 0, 0.1)), color(0, 0, 1));
+    }
+
+    #[test]
+    fn loading_a_missing_texture_file() {
+        let err = ImageTexture::from_ppm_file("no-such-texture.ppm").unwrap_err();
+        assert!(matches!(err, PpmError::Io(_)));
+    }
+}
@@ -0,0 +1,209 @@
+//! The output transform that turns the linear, unbounded colors of a
+//! render into values meant for a display: an exposure adjustment, a tone
+//! mapping curve that squeezes highlights into 0..1, and an encoding for
+//! the display's response. It is applied when an image is exported, and
+//! never changes the canvas itself.
+
+use super::prelude::*;
+
+pub fn output_transform() -> OutputTransform {
+    OutputTransform::default()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// Leaves colors alone, so anything above 1 is clipped on export.
+    #[default]
+    Clamp,
+    /// c / (1 + c), which never quite reaches white.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve, with a slight toe and
+    /// a soft shoulder.
+    Aces,
+    /// Hable's curve from Uncharted 2, after doubling the exposure. The
+    /// curve's white point is 11.2, so a linear value of 5.6 becomes white
+    /// and anything brighter is clipped.
+    Filmic,
+}
+
+impl ToneMap {
+    pub fn map(&self, v: Num) -> Num {
+        let v = v.max(0.0);
+        match self {
+            ToneMap::Clamp => v,
+            ToneMap::Reinhard => v / (1.0 + v),
+            ToneMap::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((v * (a * v + b)) / (v * (c * v + d) + e)).clamp(0.0, 1.0)
+            }
+            ToneMap::Filmic => {
+                const WHITE: Num = 11.2;
+                const BIAS: Num = 2.0;
+                (hable(v * BIAS) / hable(WHITE)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+fn hable(x: Num) -> Num {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+/// How tone mapped values are encoded for display.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// Written as they are.
+    #[default]
+    Linear,
+    /// The piecewise sRGB curve, which is what most displays and image
+    /// viewers expect.
+    Srgb,
+    /// Raised to the power of 1 / gamma, e.g. 2.2.
+    Gamma(Num),
+}
+
+impl Encoding {
+    pub fn encode(&self, v: Num) -> Num {
+        match self {
+            Encoding::Linear => v,
+            Encoding::Srgb if v <= 0.0031308 => 12.92 * v,
+            Encoding::Srgb => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+            Encoding::Gamma(gamma) => v.max(0.0).powf(1.0 / gamma),
+        }
+    }
+}
+
+/// Exposure, then tone mapping, then encoding. The default does nothing,
+/// so exports look the way they always have.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutputTransform {
+    exposure: Num,
+    tone_map: ToneMap,
+    encoding: Encoding,
+}
+
+impl OutputTransform {
+    /// Brightens (positive) or darkens (negative) the image by a number of
+    /// stops, each of which doubles or halves the light.
+    pub fn with_exposure(mut self, stops: impl Into<Num>) -> Self {
+        self.exposure = stops.into();
+        self
+    }
+
+    pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.tone_map = tone_map;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn exposure(&self) -> Num {
+        self.exposure
+    }
+
+    pub fn tone_map(&self) -> ToneMap {
+        self.tone_map
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, c: Color) -> Color {
+        if self.is_identity() {
+            return c;
+        }
+        let scale = self.exposure.exp2();
+        let f = |v: Num| self.encoding.encode(self.tone_map.map(v * scale));
+        color(f(c.r()), f(c.g()), f(c.b()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_transform_changes_nothing() {
+        let t = output_transform();
+        assert!(t.is_identity());
+        assert_eq!(t.apply(color(1.5, -0.5, 0.25)), color(1.5, -0.5, 0.25));
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let t = output_transform().with_exposure(1);
+        assert_eq!(t.apply(color(0.25, 0.5, 0)), color(0.5, 1, 0));
+        let t = output_transform().with_exposure(-2);
+        assert_eq!(t.apply(color(1, 2, 4)), color(0.25, 0.5, 1));
+    }
+
+    #[test]
+    fn reinhard_tone_mapping() {
+        let t = output_transform().with_tone_map(ToneMap::Reinhard);
+        assert_eq!(t.apply(color(0, 1, 3)), color(0, 0.5, 0.75));
+        assert!(ToneMap::Reinhard.map(1000.0) < 1.0);
+    }
+
+    #[test]
+    fn aces_tone_mapping() {
+        assert_eq!(ToneMap::Aces.map(0.0), 0.0);
+        assert!(nums_equal(ToneMap::Aces.map(1.0), 0.80380));
+        assert_eq!(ToneMap::Aces.map(100.0), 1.0);
+        assert_eq!(ToneMap::Aces.map(-1.0), 0.0);
+    }
+
+    #[test]
+    fn filmic_tone_mapping() {
+        assert!(nums_equal(ToneMap::Filmic.map(0.0), 0.0));
+        assert!(nums_equal(ToneMap::Filmic.map(5.6), 1.0));
+        assert_eq!(ToneMap::Filmic.map(100.0), 1.0);
+        let (a, b) = (ToneMap::Filmic.map(0.2), ToneMap::Filmic.map(0.4));
+        assert!(0.0 < a && a < b && b < 1.0);
+    }
+
+    #[test]
+    fn tone_maps_keep_the_order_of_values() {
+        for tone_map in [ToneMap::Reinhard, ToneMap::Aces, ToneMap::Filmic] {
+            let values = (0..50).map(|i| tone_map.map(i as Num * 0.2)).collect_vec();
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "{tone_map:?}");
+        }
+    }
+
+    #[test]
+    fn srgb_encoding() {
+        let e = Encoding::Srgb;
+        assert_eq!(e.encode(0.0), 0.0);
+        assert!(nums_equal(e.encode(0.002), 0.02584));
+        assert!(nums_equal(e.encode(0.5), 0.73536));
+        assert!(nums_equal(e.encode(1.0), 1.0));
+        // the two pieces of the curve meet
+        assert!((e.encode(0.0031308) - e.encode(0.0031309)).abs() < 0.0001);
+    }
+
+    #[test]
+    fn gamma_encoding() {
+        let e = Encoding::Gamma(2.0);
+        assert_eq!(e.encode(0.25), 0.5);
+        assert_eq!(e.encode(-1.0), 0.0);
+    }
+
+    #[test]
+    fn exposure_then_tone_mapping_then_encoding() {
+        let t = output_transform()
+            .with_exposure(1)
+            .with_tone_map(ToneMap::Reinhard)
+            .with_encoding(Encoding::Gamma(2.0));
+        // 0.5 -> 1 -> 0.5 -> 0.70711
+        let expected = std::f64::consts::FRAC_1_SQRT_2;
+        assert_eq!(t.apply(color(0.5, 0, 0)), color(expected, 0, 0));
+    }
+}
@@ -53,7 +53,7 @@ pub fn rotation_y(rad: impl Into<Num>) -> Matrix {
 pub fn rotation_z(rad: impl Into<Num>) -> Matrix {
     let rad = rad.into();
     let mut dst = identity();
-    dst.set(0, 0, -Num::cos(rad));
+    dst.set(0, 0, Num::cos(rad));
     dst.set(0, 1, -Num::sin(rad));
     dst.set(1, 0, Num::sin(rad));
     dst.set(1, 1, Num::cos(rad));
@@ -84,6 +84,20 @@ pub fn shearing(
     dst
 }
 
+/// Orients the world relative to an eye positioned at `from`, looking at `to`.
+pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix {
+    let forward = (to - from).normalize();
+    let left = forward.cross(up.normalize());
+    let true_up = left.cross(forward);
+    let mut orientation = identity();
+    for (row, v) in [left, true_up, -forward].into_iter().enumerate() {
+        orientation.set(row, 0, v.x());
+        orientation.set(row, 1, v.y());
+        orientation.set(row, 2, v.z());
+    }
+    orientation.mul_matrix(translation(-from.x(), -from.y(), -from.z()))
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -270,4 +284,50 @@ mod tests {
             .translation(10, 5, 7);
         assert_eq!(xf.mul_point(p), point(15, 0, 7));
     }
+
+    #[test]
+    fn the_transformation_matrix_for_the_default_orientation() {
+        let from = point(0, 0, 0);
+        let to = point(0, 0, -1);
+        let up = vector(0, 1, 0);
+        assert_eq!(view_transform(from, to, up), identity());
+    }
+
+    #[test]
+    fn a_view_transformation_matrix_looking_in_positive_z_direction() {
+        let from = point(0, 0, 0);
+        let to = point(0, 0, 1);
+        let up = vector(0, 1, 0);
+        assert_eq!(view_transform(from, to, up), scaling(-1, 1, -1));
+    }
+
+    #[test]
+    fn the_view_transformation_moves_the_world() {
+        let from = point(0, 0, 8);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        assert_eq!(view_transform(from, to, up), translation(0, 0, -8));
+    }
+
+    #[test]
+    fn an_arbitrary_view_transformation() {
+        let from = point(1, 3, 2);
+        let to = point(4, -2, 8);
+        let up = vector(1, 1, 0);
+        let mut expected = identity();
+        for (row, vals) in [
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.00000],
+            [0.00000, 0.00000, 0.00000, 1.00000],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            for (col, val) in vals.into_iter().enumerate() {
+                expected.set(row, col, val);
+            }
+        }
+        assert_eq!(view_transform(from, to, up), expected);
+    }
 }
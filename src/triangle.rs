@@ -0,0 +1,190 @@
+use super::prelude::*;
+
+pub fn triangle(p1: Point, p2: Point, p3: Point) -> Triangle {
+    Triangle::new(p1, p2, p3)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+    props: ShapeProps,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = e2.cross(e1).normalize();
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+            props: ShapeProps::default(),
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+
+    pub fn e1(&self) -> Vector {
+        self.e1
+    }
+
+    pub fn e2(&self) -> Vector {
+        self.e2
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+}
+
+impl Shape for Triangle {
+    fn props(&self) -> &ShapeProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        &mut self.props
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => intersections([intersection(t, self).with_uv(u, v)]),
+            None => Intersections::default(),
+        }
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::empty()
+            .with_point(self.p1)
+            .with_point(self.p2)
+            .with_point(self.p3)
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        self.normal
+    }
+}
+
+/// Intersects a ray with the triangle described by a vertex and the two edges
+/// leaving it, returning t along with the barycentric u and v of the hit.
+pub(crate) fn moller_trumbore(
+    p1: Point,
+    e1: Vector,
+    e2: Vector,
+    ray: Ray,
+) -> Option<(Num, Num, Num)> {
+    let dir_cross_e2 = ray.dir().cross(e2);
+    let det = e1.dot(dir_cross_e2);
+    if det.abs() < EPSILON {
+        // the ray is parallel to the triangle
+        return None;
+    }
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin() - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.dir().dot(origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
+    }
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_triangle() -> Triangle {
+        triangle(point(0, 1, 0), point(-1, 0, 0), point(1, 0, 0))
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let p1 = point(0, 1, 0);
+        let p2 = point(-1, 0, 0);
+        let p3 = point(1, 0, 0);
+        let t = triangle(p1, p2, p3);
+        assert_eq!(t.p1(), p1);
+        assert_eq!(t.p2(), p2);
+        assert_eq!(t.p3(), p3);
+        assert_eq!(t.e1(), vector(-1, -1, 0));
+        assert_eq!(t.e2(), vector(1, -1, 0));
+        assert_eq!(t.normal(), vector(0, 0, -1));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = test_triangle();
+        assert_eq!(t.local_normal_at(point(0, 0.5, 0)), t.normal());
+        assert_eq!(t.local_normal_at(point(-0.5, 0.75, 0)), t.normal());
+        assert_eq!(t.local_normal_at(point(0.5, 0.25, 0)), t.normal());
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let t = test_triangle();
+        let r = ray(point(0, -1, -2), vector(0, 1, 0));
+        assert!(t.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_misses_the_p1_p3_edge() {
+        let t = test_triangle();
+        let r = ray(point(1, 1, -2), vector(0, 0, 1));
+        assert!(t.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_misses_the_p1_p2_edge() {
+        let t = test_triangle();
+        let r = ray(point(-1, 1, -2), vector(0, 0, 1));
+        assert!(t.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_misses_the_p2_p3_edge() {
+        let t = test_triangle();
+        let r = ray(point(0, -1, -2), vector(0, 0, 1));
+        assert!(t.local_intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_strikes_a_triangle() {
+        let t = test_triangle();
+        let r = ray(point(0, 0.5, -2), vector(0, 0, 1));
+        let xs = t.local_intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t(), 2.0);
+    }
+
+    #[test]
+    fn a_triangle_has_a_bounding_box() {
+        let t = triangle(point(-3, 7, 2), point(6, 2, -4), point(2, -1, -1));
+        let b = t.local_bounds();
+        assert_eq!(b.min(), point(-3, -1, -4));
+        assert_eq!(b.max(), point(6, 7, 2));
+    }
+}
@@ -0,0 +1,475 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+use super::prelude::*;
+
+pub fn uv_checkers(width: usize, height: usize, a: Color, b: Color) -> UvCheckers {
+    UvCheckers::new(width, height, a, b)
+}
+
+pub fn uv_align_check(main: Color, ul: Color, ur: Color, bl: Color, br: Color) -> UvAlignCheck {
+    UvAlignCheck {
+        main,
+        ul,
+        ur,
+        bl,
+        br,
+    }
+}
+
+pub fn texture_map_pattern(
+    uv_pattern: impl UvPattern + 'static,
+    mapping: UvMapping,
+) -> TextureMapPattern {
+    TextureMapPattern::new(uv_pattern, mapping)
+}
+
+pub fn cube_map_pattern(faces: [Arc<dyn UvPattern>; 6]) -> CubeMapPattern {
+    CubeMapPattern::new(faces)
+}
+
+/// Maps a point on the unit sphere to u and v, where u goes once around
+/// the equator and v runs from the south pole (0) to the north pole (1).
+pub fn spherical_map(point: Point) -> (Num, Num) {
+    let theta = point.x().atan2(point.z());
+    let radius = vector(point.x(), point.y(), point.z()).magnitude();
+    let phi = (point.y() / radius).acos();
+    let raw_u = theta / (2.0 * PI);
+    // flip u so that it increases counterclockwise when viewed from above
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+/// Maps a point on the xz plane to u and v, repeating every unit.
+pub fn planar_map(point: Point) -> (Num, Num) {
+    (point.x().rem_euclid(1.0), point.z().rem_euclid(1.0))
+}
+
+/// Maps a point on the unit cylinder to u and v, where u goes once around
+/// the cylinder and v repeats every unit along y.
+pub fn cylindrical_map(point: Point) -> (Num, Num) {
+    let theta = point.x().atan2(point.z());
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (u, point.y().rem_euclid(1.0))
+}
+
+/// Maps a point on the unit cube to u and v on whichever face it lies on.
+pub fn cube_map(point: Point) -> (Num, Num) {
+    CubeFace::from_point(point).uv(point)
+}
+
+/// How a point in object space is turned into two dimensional texture
+/// coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cube,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: Point) -> (Num, Num) {
+        match self {
+            Self::Spherical => spherical_map(point),
+            Self::Planar => planar_map(point),
+            Self::Cylindrical => cylindrical_map(point),
+            Self::Cube => cube_map(point),
+        }
+    }
+}
+
+/// The faces of the unit cube, in the order `CubeMapPattern` expects them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    pub fn from_point(point: Point) -> Self {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let coord = x.abs().max(y.abs()).max(z.abs());
+        if coord == x {
+            Self::Right
+        } else if coord == -x {
+            Self::Left
+        } else if coord == y {
+            Self::Up
+        } else if coord == -y {
+            Self::Down
+        } else if coord == z {
+            Self::Front
+        } else {
+            Self::Back
+        }
+    }
+
+    /// The u and v of a point on this face, as seen from outside the cube
+    /// with "up" being +y, or -z for the top and +z for the bottom.
+    pub fn uv(&self, point: Point) -> (Num, Num) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let wrap = |n: Num| n.rem_euclid(2.0) / 2.0;
+        match self {
+            Self::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+            Self::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+            Self::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+            Self::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+            Self::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+            Self::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+        }
+    }
+}
+
+/// A pattern in two dimensions, with u and v both between 0 and 1.
+pub trait UvPattern: fmt::Debug + Send + Sync {
+    fn uv_color_at(&self, u: Num, v: Num) -> Color;
+}
+
+/// A checkerboard with `width` squares along u and `height` along v.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UvCheckers {
+    width: usize,
+    height: usize,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: usize, height: usize, a: Color, b: Color) -> Self {
+        Self {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+}
+
+impl UvPattern for UvCheckers {
+    fn uv_color_at(&self, u: Num, v: Num) -> Color {
+        let u2 = (u * self.width as Num).floor() as i64;
+        let v2 = (v * self.height as Num).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// A solid color with differently colored corners, which makes it easy to
+/// see how a texture is oriented on a surface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UvAlignCheck {
+    main: Color,
+    ul: Color,
+    ur: Color,
+    bl: Color,
+    br: Color,
+}
+
+impl UvPattern for UvAlignCheck {
+    fn uv_color_at(&self, u: Num, v: Num) -> Color {
+        match (u < 0.2, u > 0.8, v < 0.2, v > 0.8) {
+            (true, _, _, true) => self.ul,
+            (_, true, _, true) => self.ur,
+            (true, _, true, _) => self.bl,
+            (_, true, true, _) => self.br,
+            _ => self.main,
+        }
+    }
+}
+
+/// Wraps a two dimensional pattern around a shape using a UV mapping.
+#[derive(Clone, Debug)]
+pub struct TextureMapPattern {
+    props: PatternProps,
+    uv_pattern: Arc<dyn UvPattern>,
+    mapping: UvMapping,
+}
+
+impl TextureMapPattern {
+    pub fn new(uv_pattern: impl UvPattern + 'static, mapping: UvMapping) -> Self {
+        Self {
+            props: PatternProps::default(),
+            uv_pattern: Arc::new(uv_pattern),
+            mapping,
+        }
+    }
+
+    pub fn mapping(&self) -> UvMapping {
+        self.mapping
+    }
+}
+
+impl Pattern for TextureMapPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_color_at(u, v)
+    }
+}
+
+/// Paints each face of the unit cube with its own two dimensional pattern.
+#[derive(Clone, Debug)]
+pub struct CubeMapPattern {
+    props: PatternProps,
+    faces: [Arc<dyn UvPattern>; 6],
+}
+
+impl CubeMapPattern {
+    /// The faces are given in `CubeFace` order: left, front, right, back,
+    /// up and down.
+    pub fn new(faces: [Arc<dyn UvPattern>; 6]) -> Self {
+        Self {
+            props: PatternProps::default(),
+            faces,
+        }
+    }
+}
+
+impl Pattern for CubeMapPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.uv(point);
+        self.faces[face as usize].uv_color_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    fn white() -> Color {
+        color(1, 1, 1)
+    }
+
+    fn black() -> Color {
+        color(0, 0, 0)
+    }
+
+    fn assert_uv(actual: (Num, Num), expected: (Num, Num)) {
+        assert!(
+            nums_equal(actual.0, expected.0) && nums_equal(actual.1, expected.1),
+            "expected {expected:?} but was {actual:?}"
+        );
+    }
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let checkers = uv_checkers(2, 2, black(), white());
+        assert_eq!(checkers.uv_color_at(0.0, 0.0), black());
+        assert_eq!(checkers.uv_color_at(0.5, 0.0), white());
+        assert_eq!(checkers.uv_color_at(0.0, 0.5), white());
+        assert_eq!(checkers.uv_color_at(0.5, 0.5), black());
+        assert_eq!(checkers.uv_color_at(1.0, 1.0), black());
+    }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let cases = [
+            (point(0, 0, -1), (0.0, 0.5)),
+            (point(1, 0, 0), (0.25, 0.5)),
+            (point(0, 0, 1), (0.5, 0.5)),
+            (point(-1, 0, 0), (0.75, 0.5)),
+            (point(0, 1, 0), (0.5, 1.0)),
+            (point(0, -1, 0), (0.5, 0.0)),
+            (point(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0), (0.25, 0.75)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(spherical_map(p), uv);
+        }
+    }
+
+    #[test]
+    fn using_a_texture_map_pattern_with_a_spherical_map() {
+        let pattern =
+            texture_map_pattern(uv_checkers(16, 8, black(), white()), UvMapping::Spherical);
+        let cases = [
+            (point(0.4315, 0.4670, 0.7719), white()),
+            (point(-0.9654, 0.2552, -0.0534), black()),
+            (point(0.1039, 0.7090, 0.6975), white()),
+            (point(-0.4986, -0.7856, -0.3663), black()),
+            (point(-0.0317, -0.9395, 0.3411), black()),
+            (point(0.4809, -0.7721, 0.4154), black()),
+            (point(0.0285, -0.9612, -0.2745), black()),
+            (point(-0.5734, -0.2162, -0.7903), white()),
+            (point(0.7688, -0.1470, 0.6223), black()),
+            (point(-0.7652, 0.2175, 0.6060), black()),
+        ];
+        for (p, expected) in cases {
+            assert_eq!(pattern.color_at(p), expected, "at {p:?}");
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let cases = [
+            (point(0.25, 0, 0.5), (0.25, 0.5)),
+            (point(0.25, 0, -0.25), (0.25, 0.75)),
+            (point(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (point(1.25, 0, 0.5), (0.25, 0.5)),
+            (point(0.25, 0, -1.75), (0.25, 0.25)),
+            (point(1, 0, -1), (0.0, 0.0)),
+            (point(0, 0, 0), (0.0, 0.0)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(planar_map(p), uv);
+        }
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let cases = [
+            (point(0, 0, -1), (0.0, 0.0)),
+            (point(0, 0.5, -1), (0.0, 0.5)),
+            (point(0, 1, -1), (0.0, 0.0)),
+            (point(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), (0.125, 0.5)),
+            (point(1, 0.5, 0), (0.25, 0.5)),
+            (point(FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), (0.375, 0.5)),
+            (point(0, -0.25, 1), (0.5, 0.75)),
+            (point(-FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), (0.625, 0.5)),
+            (point(-1, 1.25, 0), (0.75, 0.25)),
+            (point(-FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), (0.875, 0.5)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(cylindrical_map(p), uv);
+        }
+    }
+
+    #[test]
+    fn layout_of_the_align_check_pattern() {
+        let main = color(1, 1, 1);
+        let ul = color(1, 0, 0);
+        let ur = color(1, 1, 0);
+        let bl = color(0, 1, 0);
+        let br = color(0, 1, 1);
+        let pattern = uv_align_check(main, ul, ur, bl, br);
+        assert_eq!(pattern.uv_color_at(0.5, 0.5), main);
+        assert_eq!(pattern.uv_color_at(0.1, 0.9), ul);
+        assert_eq!(pattern.uv_color_at(0.9, 0.9), ur);
+        assert_eq!(pattern.uv_color_at(0.1, 0.1), bl);
+        assert_eq!(pattern.uv_color_at(0.9, 0.1), br);
+    }
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let cases = [
+            (point(-1, 0.5, -0.25), CubeFace::Left),
+            (point(1.1, -0.75, 0.8), CubeFace::Right),
+            (point(0.1, 0.6, 0.9), CubeFace::Front),
+            (point(-0.7, 0, -2), CubeFace::Back),
+            (point(0.5, 1, 0.9), CubeFace::Up),
+            (point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (p, face) in cases {
+            assert_eq!(CubeFace::from_point(p), face, "at {p:?}");
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let cases = [
+            (CubeFace::Front, point(-0.5, 0.5, 1), (0.25, 0.75)),
+            (CubeFace::Front, point(0.5, -0.5, 1), (0.75, 0.25)),
+            (CubeFace::Back, point(0.5, 0.5, -1), (0.25, 0.75)),
+            (CubeFace::Back, point(-0.5, -0.5, -1), (0.75, 0.25)),
+            (CubeFace::Left, point(-1, 0.5, -0.5), (0.25, 0.75)),
+            (CubeFace::Left, point(-1, -0.5, 0.5), (0.75, 0.25)),
+            (CubeFace::Right, point(1, 0.5, 0.5), (0.25, 0.75)),
+            (CubeFace::Right, point(1, -0.5, -0.5), (0.75, 0.25)),
+            (CubeFace::Up, point(-0.5, 1, -0.5), (0.25, 0.75)),
+            (CubeFace::Up, point(0.5, 1, 0.5), (0.75, 0.25)),
+            (CubeFace::Down, point(-0.5, -1, 0.5), (0.25, 0.75)),
+            (CubeFace::Down, point(0.5, -1, -0.5), (0.75, 0.25)),
+        ];
+        for (face, p, uv) in cases {
+            assert_uv(face.uv(p), uv);
+            assert_uv(cube_map(p), uv);
+        }
+    }
+
+    #[test]
+    fn finding_the_colors_on_a_mapped_cube() {
+        let red = color(1, 0, 0);
+        let yellow = color(1, 1, 0);
+        let brown = color(1, 0.5, 0);
+        let green = color(0, 1, 0);
+        let cyan = color(0, 1, 1);
+        let blue = color(0, 0, 1);
+        let purple = color(1, 0, 1);
+        let white = color(1, 1, 1);
+        let face = |main, ul, ur, bl, br| -> Arc<dyn UvPattern> {
+            Arc::new(uv_align_check(main, ul, ur, bl, br))
+        };
+        let pattern = cube_map_pattern([
+            face(yellow, cyan, red, blue, brown),
+            face(cyan, red, yellow, brown, green),
+            face(red, yellow, purple, green, white),
+            face(green, purple, cyan, white, blue),
+            face(brown, cyan, purple, red, yellow),
+            face(purple, brown, green, blue, white),
+        ]);
+        let cases = [
+            // left
+            (point(-1, 0, 0), yellow),
+            (point(-1, 0.9, -0.9), cyan),
+            (point(-1, 0.9, 0.9), red),
+            (point(-1, -0.9, -0.9), blue),
+            (point(-1, -0.9, 0.9), brown),
+            // front
+            (point(0, 0, 1), cyan),
+            (point(-0.9, 0.9, 1), red),
+            (point(0.9, 0.9, 1), yellow),
+            (point(-0.9, -0.9, 1), brown),
+            (point(0.9, -0.9, 1), green),
+            // up
+            (point(0, 1, 0), brown),
+            (point(-0.9, 1, -0.9), cyan),
+            (point(0.9, 1, -0.9), purple),
+            (point(-0.9, 1, 0.9), red),
+            (point(0.9, 1, 0.9), yellow),
+            // down
+            (point(0, -1, 0), purple),
+            (point(-0.9, -1, 0.9), brown),
+            (point(0.9, -1, 0.9), green),
+            (point(-0.9, -1, -0.9), blue),
+            (point(0.9, -1, -0.9), white),
+        ];
+        for (p, expected) in cases {
+            assert_eq!(pattern.color_at(p), expected, "at {p:?}");
+        }
+    }
+
+    #[test]
+    fn a_texture_map_follows_the_shape_it_is_painted_on() {
+        let pattern = texture_map_pattern(uv_checkers(2, 1, black(), white()), UvMapping::Planar);
+        let shape = plane().with_transform(scaling(2, 2, 2));
+        assert_eq!(pattern.color_at_shape(&shape, point(0.5, 0, 0.5)), black());
+        assert_eq!(pattern.color_at_shape(&shape, point(1.5, 0, 0.5)), white());
+    }
+}
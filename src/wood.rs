@@ -0,0 +1,94 @@
+use super::prelude::*;
+
+pub fn wood_pattern(a: Color, b: Color, noise: Noise) -> WoodPattern {
+    WoodPattern::new(a, b, noise)
+}
+
+/// Growth rings around the y axis that fade from `a` to `b`, distorted by
+/// fractal noise so that they wander like the grain in a plank.
+#[derive(Clone, Debug)]
+pub struct WoodPattern {
+    props: PatternProps,
+    a: Color,
+    b: Color,
+    noise: Noise,
+    rings: Num,
+    grain: Num,
+    octaves: usize,
+}
+
+impl WoodPattern {
+    pub fn new(a: Color, b: Color, noise: Noise) -> Self {
+        Self {
+            props: PatternProps::default(),
+            a,
+            b,
+            noise,
+            rings: 4.0,
+            grain: 0.1,
+            octaves: 3,
+        }
+    }
+
+    /// How many rings there are per unit of distance from the y axis.
+    pub fn with_rings(mut self, rings: impl Into<Num>) -> Self {
+        self.rings = rings.into();
+        self
+    }
+
+    /// How far the noise may push a point towards or away from the axis.
+    pub fn with_grain(mut self, grain: impl Into<Num>) -> Self {
+        self.grain = grain.into();
+        self
+    }
+
+    pub fn with_octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl Pattern for WoodPattern {
+    fn props(&self) -> &PatternProps {
+        &self.props
+    }
+
+    fn props_mut(&mut self) -> &mut PatternProps {
+        &mut self.props
+    }
+
+    fn local_color_at(&self, point: Point) -> Color {
+        let distance =
+            point.x().hypot(point.z()) + self.noise.fbm(point, self.octaves) * self.grain;
+        let t = (distance * self.rings).rem_euclid(1.0);
+        self.a + (self.b - self.a).mul_scalar(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wood_without_grain_is_concentric_rings() {
+        let pattern = wood_pattern(color(0, 0, 0), color(1, 1, 1), noise(1))
+            .with_grain(0)
+            .with_rings(1);
+        assert_eq!(pattern.color_at(point(0, 0, 0)), color(0, 0, 0));
+        assert_eq!(pattern.color_at(point(0.5, 3, 0)), color(0.5, 0.5, 0.5));
+        assert_eq!(
+            pattern.color_at(point(0, -2, 1.25)),
+            color(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn wood_grain_distorts_the_rings() {
+        let plain = wood_pattern(color(0, 0, 0), color(1, 1, 1), noise(2)).with_grain(0);
+        let grained = wood_pattern(color(0, 0, 0), color(1, 1, 1), noise(2)).with_grain(0.3);
+        let differs = (0..50)
+            .map(|i| point(i as Num * 0.17 + 0.1, 0.3, i as Num * 0.05))
+            .any(|p| plain.color_at(p) != grained.color_at(p));
+        assert!(differs);
+    }
+}
@@ -0,0 +1,573 @@
+use super::prelude::*;
+use crate::stats;
+
+pub fn world() -> World {
+    World::default()
+}
+
+/// The two concentric spheres lit from the upper left that the book uses
+/// throughout its scene tests.
+pub fn default_world() -> World {
+    let light = point_light(point(-10, 10, -10), color(1, 1, 1));
+    let s1 = sphere().with_material(
+        material()
+            .with_color(color(0.8, 1.0, 0.6))
+            .with_diffuse(0.7)
+            .with_specular(0.2),
+    );
+    let s2 = sphere().with_transform(scaling(0.5, 0.5, 0.5));
+    world().with_light(light).with_object(s1).with_object(s2)
+}
+
+/// How many times a ray may bounce between reflective surfaces before
+/// tracing gives up, unless the world says otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+#[derive(Debug)]
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<PointLight>,
+    /// Limits the recursion of reflected rays so that two mirrors facing
+    /// each other don't bounce a ray forever.
+    pub max_depth: usize,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            objects: Vec::default(),
+            lights: Vec::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl World {
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_object(mut self, object: impl Shape + 'static) -> Self {
+        self.add_object(object);
+        self
+    }
+
+    pub fn add_object(&mut self, object: impl Shape + 'static) {
+        self.objects.push(Box::new(object));
+    }
+
+    pub fn with_light(mut self, light: PointLight) -> Self {
+        self.add_light(light);
+        self
+    }
+
+    pub fn add_light(&mut self, light: PointLight) {
+        self.lights.push(light);
+    }
+
+    /// Gathers the objects into a bounding volume hierarchy so that rays
+    /// only get tested against objects whose bounding boxes they cross.
+    /// Objects that are unbounded, like planes, stay at the top level.
+    ///
+    /// Groups made from OBJ files are already divided, but worlds built by
+    /// hand aren't: call this once the objects are in place (e.g. with
+    /// `DEFAULT_DIVIDE_THRESHOLD`) before rendering a large scene.
+    pub fn divide(&mut self, threshold: usize) {
+        let (bounded, mut objects): (Vec<_>, Vec<_>) = std::mem::take(&mut self.objects)
+            .into_iter()
+            .partition(|o| o.parent_space_bounds().is_finite());
+        if !bounded.is_empty() {
+            let mut root = group();
+            for object in bounded {
+                root.add_boxed_child(object);
+            }
+            root.divide(threshold);
+            objects.push(Box::new(root));
+        }
+        self.objects = objects;
+    }
+
+    /// Intersects the ray with every object in the world, returning the
+    /// intersections sorted by t.
+    pub fn intersect(&self, ray: Ray) -> Intersections<'_> {
+        stats::record_ray();
+        self.objects
+            .iter()
+            .flat_map(|o| o.intersect(ray))
+            .collect::<Intersections>()
+            .sorted()
+    }
+
+    /// Shades the hit, following reflected and refracted rays for at most
+    /// `remaining` more bounces.
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        let surface = self
+            .lights
+            .iter()
+            .map(|light| {
+                let in_shadow = self.is_shadowed(light.position, comps.over_point);
+                lighting(
+                    comps.object.material(),
+                    comps.object,
+                    light,
+                    comps.over_point,
+                    comps.eyev,
+                    comps.normalv,
+                    in_shadow,
+                )
+            })
+            .fold(color(0, 0, 0), |acc, c| acc + c);
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+        let material = comps.object.material();
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected.mul_scalar(reflectance) + refracted.mul_scalar(1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// The color seen in the surface's reflection, which is black for
+    /// surfaces that aren't reflective or once the bounces run out.
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if reflective == 0.0 || remaining == 0 {
+            return color(0, 0, 0);
+        }
+        let reflect_ray = ray(comps.over_point, comps.reflectv);
+        self.color_at_depth(reflect_ray, remaining - 1)
+            .mul_scalar(reflective)
+    }
+
+    /// Reports whether anything lies between the point and the light.
+    pub fn is_shadowed(&self, light_position: Point, point: Point) -> bool {
+        let v = light_position - point;
+        let distance = v.magnitude();
+        let r = ray(point, v.normalize());
+        match self.intersect(r).hit() {
+            Some(hit) => hit.t() < distance,
+            None => false,
+        }
+    }
+
+    /// The color seen through a transparent surface, bent according to
+    /// Snell's law. Black for opaque surfaces, once the bounces run out, or
+    /// when the light is totally internally reflected.
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let transparency = comps.object.material().transparency;
+        if transparency == 0.0 || remaining == 0 {
+            return color(0, 0, 0);
+        }
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(comps.normalv);
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        if sin2_t > 1.0 {
+            return color(0, 0, 0);
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction =
+            comps.normalv.mul_scalar(n_ratio * cos_i - cos_t) - comps.eyev.mul_scalar(n_ratio);
+        let refract_ray = ray(comps.under_point, direction);
+        self.color_at_depth(refract_ray, remaining - 1)
+            .mul_scalar(transparency)
+    }
+
+    pub fn color_at(&self, ray: Ray) -> Color {
+        self.color_at_depth(ray, self.max_depth)
+    }
+
+    /// Like `color_at`, but with an explicit number of remaining bounces.
+    pub fn color_at_depth(&self, ray: Ray, remaining: usize) -> Color {
+        let xs = self.intersect(ray);
+        match xs.hit() {
+            Some(hit) => self.shade_hit(&hit.prepare_computations(ray, &xs), remaining),
+            None => color(0, 0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::tests::test_pattern;
+    use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+    #[test]
+    fn creating_a_world() {
+        let w = world();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+    }
+
+    #[test]
+    fn the_default_world() {
+        let light = point_light(point(-10, 10, -10), color(1, 1, 1));
+        let s1 = sphere().with_material(
+            material()
+                .with_color(color(0.8, 1.0, 0.6))
+                .with_diffuse(0.7)
+                .with_specular(0.2),
+        );
+        let s2 = sphere().with_transform(scaling(0.5, 0.5, 0.5));
+        let w = default_world();
+        assert_eq!(w.lights, vec![light]);
+        assert_eq!(w.objects.len(), 2);
+        for (object, expected) in w.objects.iter().zip([s1, s2]) {
+            assert_eq!(object.transform(), expected.transform());
+            assert_eq!(object.material(), expected.material());
+        }
+    }
+
+    #[test]
+    fn intersect_a_world_with_a_ray() {
+        let w = default_world();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = w.intersect(r);
+        assert_eq!(
+            xs.iter().map(|i| i.t()).collect_vec(),
+            vec![4.0, 4.5, 5.5, 6.0]
+        );
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let shape = w.objects[0].as_ref();
+        let i = intersection(4, shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert_eq!(c, color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let mut w = default_world();
+        w.lights = vec![point_light(point(0, 0.25, 0), color(1, 1, 1))];
+        let r = ray(point(0, 0, 0), vector(0, 0, 1));
+        let shape = w.objects[1].as_ref();
+        let i = intersection(0.5, shape);
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert_eq!(c, color(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn shading_with_multiple_lights_sums_their_contributions() {
+        let mut w = default_world();
+        w.add_light(w.lights[0]);
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let i = intersection(4, w.objects[0].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert_eq!(c, color(0.38066, 0.47583, 0.2855).mul_scalar(2));
+    }
+
+    #[test]
+    fn the_color_when_a_ray_misses() {
+        let w = default_world();
+        let r = ray(point(0, 0, -5), vector(0, 1, 0));
+        let c = w.color_at(r);
+        assert_eq!(c, color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_color_when_a_ray_hits() {
+        let w = default_world();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let c = w.color_at(r);
+        assert_eq!(c, color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn the_color_with_an_intersection_behind_the_ray() {
+        let mut w = default_world();
+        let outer = w.objects[0].material().clone();
+        w.objects[0].set_material(outer.with_ambient(1));
+        let inner = w.objects[1].material().clone();
+        w.objects[1].set_material(inner.clone().with_ambient(1));
+        let r = ray(point(0, 0, 0.75), vector(0, 0, -1));
+        let c = w.color_at(r);
+        assert_eq!(c, inner.color);
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let w = default_world();
+        let light_position = point(-10, 10, -10);
+        assert!(!w.is_shadowed(light_position, point(0, 10, 0)));
+    }
+
+    #[test]
+    fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
+        let w = default_world();
+        let light_position = point(-10, 10, -10);
+        assert!(w.is_shadowed(light_position, point(10, -10, 10)));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_light() {
+        let w = default_world();
+        let light_position = point(-10, 10, -10);
+        assert!(!w.is_shadowed(light_position, point(-20, 20, -20)));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_point() {
+        let w = default_world();
+        let light_position = point(-10, 10, -10);
+        assert!(!w.is_shadowed(light_position, point(-2, 2, -2)));
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
+        let s1 = sphere();
+        let s2 = sphere().with_transform(translation(0, 0, 10));
+        let w = world()
+            .with_light(point_light(point(0, 0, -10), color(1, 1, 1)))
+            .with_object(s1)
+            .with_object(s2);
+        let r = ray(point(0, 0, 5), vector(0, 0, 1));
+        let i = intersection(4, w.objects[1].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert_eq!(c, color(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn dividing_a_world_keeps_unbounded_objects_at_the_top_level() {
+        let mut w = default_world().with_object(plane());
+        w.divide(1);
+        assert_eq!(w.objects.len(), 2);
+        assert!(w.objects[0].children().is_empty());
+        assert_eq!(w.objects[1].primitive_count(), 2);
+    }
+
+    #[test]
+    fn dividing_a_world_does_not_change_what_rays_see() {
+        let mut w = default_world();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let before = w.color_at(r);
+        w.divide(1);
+        assert_eq!(w.color_at(r), before);
+        assert_eq!(w.color_at(r), color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_nonreflective_material() {
+        let mut w = default_world();
+        let r = ray(point(0, 0, 0), vector(0, 0, 1));
+        let inner = w.objects[1].material().clone();
+        w.objects[1].set_material(inner.clone().with_ambient(1));
+        let i = intersection(1, w.objects[1].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_reflective_material() {
+        let w = default_world().with_object(
+            plane()
+                .with_material(material().with_reflective(0.5))
+                .with_transform(translation(0, -1, 0)),
+        );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = intersection(SQRT_2, w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+        // the book's (0.19032, 0.2379, 0.14274) assumes a larger EPSILON
+        assert_eq!(c, color(0.19033, 0.23792, 0.14275));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let w = default_world().with_object(
+            plane()
+                .with_material(material().with_reflective(0.5))
+                .with_transform(translation(0, -1, 0)),
+        );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = intersection(SQRT_2, w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        let c = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        // the book's (0.87677, 0.92436, 0.82918) assumes a larger EPSILON
+        assert_eq!(c, color(0.87676, 0.92434, 0.82917));
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let w = world()
+            .with_light(point_light(point(0, 0, 0), color(1, 1, 1)))
+            .with_object(
+                plane()
+                    .with_material(material().with_reflective(1))
+                    .with_transform(translation(0, -1, 0)),
+            )
+            .with_object(
+                plane()
+                    .with_material(material().with_reflective(1))
+                    .with_transform(translation(0, 1, 0)),
+            );
+        let r = ray(point(0, 0, 0), vector(0, 1, 0));
+        // terminates rather than overflowing the stack
+        let c = w.color_at(r);
+        assert!(c.r() > 0.0);
+    }
+
+    #[test]
+    fn the_reflected_color_at_the_maximum_recursive_depth() {
+        let w = default_world().with_object(
+            plane()
+                .with_material(material().with_reflective(0.5))
+                .with_transform(translation(0, -1, 0)),
+        );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = intersection(SQRT_2, w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(w.reflected_color(&comps, 0), color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_world_limits_reflections_to_its_max_depth() {
+        assert_eq!(world().max_depth, DEFAULT_MAX_DEPTH);
+        let w = default_world().with_max_depth(0).with_object(
+            plane()
+                .with_material(material().with_reflective(0.5))
+                .with_transform(translation(0, -1, 0)),
+        );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = intersection(SQRT_2, w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &intersections([i]));
+        assert_eq!(w.color_at(r), w.shade_hit(&comps, 0));
+        assert_ne!(w.color_at(r), w.shade_hit(&comps, 1));
+    }
+
+    #[test]
+    fn the_refracted_color_with_an_opaque_surface() {
+        let w = default_world();
+        let shape = w.objects[0].as_ref();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = intersections([intersection(4, shape), intersection(6, shape)]);
+        let comps = xs[0].prepare_computations(r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_refracted_color_at_the_maximum_recursive_depth() {
+        let mut w = default_world();
+        let m = w.objects[0]
+            .material()
+            .clone()
+            .with_transparency(1)
+            .with_refractive_index(1.5);
+        w.objects[0].set_material(m);
+        let shape = w.objects[0].as_ref();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = intersections([intersection(4, shape), intersection(6, shape)]);
+        let comps = xs[0].prepare_computations(r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        let m = w.objects[0]
+            .material()
+            .clone()
+            .with_transparency(1)
+            .with_refractive_index(1.5);
+        w.objects[0].set_material(m);
+        let shape = w.objects[0].as_ref();
+        let r = ray(point(0, 0, FRAC_1_SQRT_2), vector(0, 1, 0));
+        let xs = intersections([
+            intersection(-FRAC_1_SQRT_2, shape),
+            intersection(FRAC_1_SQRT_2, shape),
+        ]);
+        // inside the sphere, so look at the second intersection
+        let comps = xs[1].prepare_computations(r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), color(0, 0, 0));
+    }
+
+    #[test]
+    fn the_refracted_color_with_a_refracted_ray() {
+        let mut w = default_world();
+        let a = w.objects[0]
+            .material()
+            .clone()
+            .with_ambient(1)
+            .with_pattern(test_pattern());
+        w.objects[0].set_material(a);
+        let b = w.objects[1]
+            .material()
+            .clone()
+            .with_transparency(1)
+            .with_refractive_index(1.5);
+        w.objects[1].set_material(b);
+        let (a, b) = (w.objects[0].as_ref(), w.objects[1].as_ref());
+        let r = ray(point(0, 0, 0.1), vector(0, 1, 0));
+        let xs = intersections([
+            intersection(-0.9899, a),
+            intersection(-0.4899, b),
+            intersection(0.4899, b),
+            intersection(0.9899, a),
+        ]);
+        let comps = xs[2].prepare_computations(r, &xs);
+        let c = w.refracted_color(&comps, 5);
+        // the book's (0, 0.99888, 0.04725) assumes a larger EPSILON
+        assert_eq!(c, color(0, 0.99887, 0.04722));
+    }
+
+    #[test]
+    fn shade_hit_with_a_transparent_material() {
+        let w = default_world()
+            .with_object(
+                plane()
+                    .with_transform(translation(0, -1, 0))
+                    .with_material(material().with_transparency(0.5).with_refractive_index(1.5)),
+            )
+            .with_object(
+                sphere()
+                    .with_transform(translation(0, -3.5, -0.5))
+                    .with_material(material().with_color(color(1, 0, 0)).with_ambient(0.5)),
+            );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let xs = intersections([intersection(SQRT_2, w.objects[2].as_ref())]);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let c = w.shade_hit(&comps, 5);
+        assert_eq!(c, color(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_transparent_material() {
+        let w = default_world()
+            .with_object(
+                plane().with_transform(translation(0, -1, 0)).with_material(
+                    material()
+                        .with_reflective(0.5)
+                        .with_transparency(0.5)
+                        .with_refractive_index(1.5),
+                ),
+            )
+            .with_object(
+                sphere()
+                    .with_transform(translation(0, -3.5, -0.5))
+                    .with_material(material().with_color(color(1, 0, 0)).with_ambient(0.5)),
+            );
+        let r = ray(point(0, 0, -3), vector(0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let xs = intersections([intersection(SQRT_2, w.objects[2].as_ref())]);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let c = w.shade_hit(&comps, 5);
+        assert_eq!(c, color(0.93391, 0.69643, 0.69243));
+    }
+
+    #[test]
+    fn every_ray_traced_through_the_world_is_counted() {
+        let w = default_world();
+        take_stats();
+        w.color_at(ray(point(0, 0, -5), vector(0, 0, 1)));
+        // the camera ray and one shadow ray
+        assert_eq!(take_stats().rays, 2);
+    }
+}